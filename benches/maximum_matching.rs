@@ -13,6 +13,9 @@ pub fn maximum_matching_4(c: &mut Criterion) {
     c.bench_function("Maximum matching simple 4", |b| {
         b.iter(|| maximum_matching_simple(black_box(&neighbors)))
     });
+    c.bench_function("Maximum matching bitmask 4", |b| {
+        b.iter(|| maximum_matching_bitmask(black_box(&neighbors)))
+    });
 }
 
 pub fn maximum_matching_9(c: &mut Criterion) {
@@ -37,7 +40,34 @@ pub fn maximum_matching_9(c: &mut Criterion) {
     c.bench_function("Maximum matching simple 9", |b| {
         b.iter(|| maximum_matching_simple(black_box(&neighbors)))
     });
+    c.bench_function("Maximum matching bitmask 9", |b| {
+        b.iter(|| maximum_matching_bitmask(black_box(&neighbors)))
+    });
+}
+
+pub fn maximum_matching_16(c: &mut Criterion) {
+    // Two vertices can only be matched to vertex 0, so there is no perfect matching,
+    // which the simple search only notices after trying every way to match the rest.
+    let all: &[usize] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+    let mut neighbors: [&[usize]; 16] = [all; 16];
+    neighbors[14] = &[0];
+    neighbors[15] = &[0];
+
+    c.bench_function("Maximum matching push-relabel 16", |b| {
+        b.iter(|| maximum_matching_push_relabel(black_box(&neighbors)))
+    });
+    c.bench_function("Maximum matching Hopcroft-Karp 16", |b| {
+        b.iter(|| maximum_matching_hopcroft_karp(black_box(&neighbors)))
+    });
+    c.bench_function("Maximum matching bitmask 16", |b| {
+        b.iter(|| maximum_matching_bitmask(black_box(&neighbors)))
+    });
 }
 
-criterion_group!(benches, maximum_matching_4, maximum_matching_9);
+criterion_group!(
+    benches,
+    maximum_matching_4,
+    maximum_matching_9,
+    maximum_matching_16
+);
 criterion_main!(benches);
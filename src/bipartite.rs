@@ -0,0 +1,242 @@
+//! Matchings in bipartite graphs whose sides are only known at runtime and may differ in size.
+//!
+//! A graph is given by the neighbor lists of the vertices on the left side:
+//! `neighbors[a]` lists the vertices on the right side that `a` is adjacent to,
+//! which must all be less than the number of vertices on the right side.
+//!
+//! Comparing lines in [`crate::line_superiority`] uses the same Hopcroft-Karp code
+//! for square graphs whose size is known at compile time, with arrays instead of vectors.
+
+/// A matching, given as the partner of each vertex on both sides.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchingResult {
+    pub left: Vec<Option<usize>>,
+    pub right: Vec<Option<usize>>,
+}
+
+impl MatchingResult {
+    /// The number of matched pairs.
+    pub fn len(&self) -> usize {
+        self.left.iter().filter(|x| x.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left.iter().all(|x| x.is_none())
+    }
+
+    /// Returns the matched pairs as `(left, right)`, ordered by the left vertex.
+    pub fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.left
+            .iter()
+            .enumerate()
+            .filter_map(|(a, b)| b.map(|b| (a, b)))
+    }
+}
+
+/// A set of vertices that touches every edge.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VertexCover {
+    pub left: Vec<usize>,
+    pub right: Vec<usize>,
+}
+
+impl VertexCover {
+    pub fn len(&self) -> usize {
+        self.left.len() + self.right.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty() && self.right.is_empty()
+    }
+}
+
+/// Finds a maximum matching of a graph with `right` vertices on the right side
+/// using the Hopcroft-Karp algorithm.
+pub fn maximum_matching<N: AsRef<[usize]>>(neighbors: &[N], right: usize) -> MatchingResult {
+    let mut left = vec![None; neighbors.len()];
+    let mut right = vec![None; right];
+    let mut layers = vec![None; neighbors.len()];
+    hopcroft_karp(neighbors, &mut left, &mut right, &mut layers);
+    MatchingResult { left, right }
+}
+
+/// Extends the matching given by the partners `pair_for_a` of the left side and `pair_for_b`
+/// of the right side to a maximum one. `layer_a` needs as many entries as the left side
+/// and is used for the layers. The buffers are left to the caller, so that graphs of a size
+/// known at compile time can keep everything in arrays.
+pub(crate) fn hopcroft_karp<N: AsRef<[usize]>>(
+    neighbors: &[N],
+    pair_for_a: &mut [Option<usize>],
+    pair_for_b: &mut [Option<usize>],
+    layer_a: &mut [Option<usize>],
+) {
+    loop {
+        // Layer the vertices of the left side by their distance from the unmatched ones
+        // until a layer has an unmatched neighbor.
+        layer_a.fill(None);
+        let mut todo: Vec<usize> = (0..neighbors.len())
+            .filter(|a| pair_for_a[*a].is_none())
+            .collect();
+        for a in &todo {
+            layer_a[*a] = Some(0);
+        }
+        let mut found_free = false;
+        let mut layer = 0;
+        while !todo.is_empty() && !found_free {
+            let mut next = vec![];
+            for a in &todo {
+                for b in neighbors[*a].as_ref() {
+                    match pair_for_b[*b] {
+                        None => found_free = true,
+                        Some(a2) if layer_a[a2].is_none() => {
+                            layer_a[a2] = Some(layer + 1);
+                            next.push(a2);
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            todo = next;
+            layer += 1;
+        }
+        if !found_free {
+            return;
+        }
+
+        // Augment along disjoint paths that go from one layer to the next
+        for start in 0..neighbors.len() {
+            if pair_for_a[start].is_some() || layer_a[start] != Some(0) {
+                continue;
+            }
+
+            let mut stack = vec![(start, 0)];
+            while let Some((a, next_neighbor)) = stack.last_mut() {
+                let a = *a;
+                let b = match neighbors[a].as_ref().get(*next_neighbor) {
+                    Some(b) => *b,
+                    None => {
+                        // Dead end, don't come here again in this phase
+                        layer_a[a] = None;
+                        stack.pop();
+                        continue;
+                    }
+                };
+                *next_neighbor += 1;
+
+                match pair_for_b[b] {
+                    None => {
+                        for (a, next_neighbor) in stack.drain(..) {
+                            let b = neighbors[a].as_ref()[next_neighbor - 1];
+                            pair_for_a[a] = Some(b);
+                            pair_for_b[b] = Some(a);
+                            layer_a[a] = None;
+                        }
+                    }
+                    Some(a2) => {
+                        if layer_a[a2] == layer_a[a].map(|x| x + 1) {
+                            stack.push((a2, 0));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns a minimum vertex cover, given a maximum matching of the same graph.
+///
+/// By König's theorem it has one vertex per matched pair: the vertices of the left side
+/// not reachable from an unmatched one by alternating paths, and those of the right side
+/// that are reachable.
+pub fn vertex_cover<N: AsRef<[usize]>>(neighbors: &[N], matching: &MatchingResult) -> VertexCover {
+    let mut reached_a = vec![false; neighbors.len()];
+    let mut reached_b = vec![false; matching.right.len()];
+    let mut todo: Vec<usize> = (0..neighbors.len())
+        .filter(|a| matching.left[*a].is_none())
+        .collect();
+    for a in &todo {
+        reached_a[*a] = true;
+    }
+
+    while let Some(a) = todo.pop() {
+        for &b in neighbors[a].as_ref() {
+            if reached_b[b] {
+                continue;
+            }
+            reached_b[b] = true;
+            if let Some(a2) = matching.right[b] {
+                if !reached_a[a2] {
+                    reached_a[a2] = true;
+                    todo.push(a2);
+                }
+            }
+        }
+    }
+
+    VertexCover {
+        left: (0..neighbors.len()).filter(|a| !reached_a[*a]).collect(),
+        right: (0..matching.right.len())
+            .filter(|b| reached_b[*b])
+            .collect(),
+    }
+}
+
+/// Finds a maximum matching and a minimum vertex cover, which prove each other optimal.
+pub fn minimum_vertex_cover<N: AsRef<[usize]>>(
+    neighbors: &[N],
+    right: usize,
+) -> (MatchingResult, VertexCover) {
+    let matching = maximum_matching(neighbors, right);
+    let cover = vertex_cover(neighbors, &matching);
+    (matching, cover)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::*;
+
+    fn is_valid(neighbors: &[Vec<usize>], matching: &MatchingResult) -> bool {
+        matching
+            .pairs()
+            .all(|(a, b)| neighbors[a].contains(&b) && matching.right[b] == Some(a))
+            && matching.right.iter().flatten().count() == matching.len()
+    }
+
+    fn covers(neighbors: &[Vec<usize>], cover: &VertexCover) -> bool {
+        neighbors
+            .iter()
+            .enumerate()
+            .all(|(a, bs)| cover.left.contains(&a) || bs.iter().all(|b| cover.right.contains(b)))
+    }
+
+    #[test]
+    fn unbalanced() {
+        // Three vertices on the left can only use two on the right
+        let neighbors = vec![vec![0], vec![0, 1], vec![1], vec![1, 2, 3]];
+        let (matching, cover) = minimum_vertex_cover(&neighbors, 5);
+        assert_eq!(matching.len(), 3);
+        assert!(is_valid(&neighbors, &matching));
+        assert_eq!(cover.len(), 3);
+        assert!(covers(&neighbors, &cover));
+        assert_eq!(matching.right[4], None);
+
+        let (matching, cover) = minimum_vertex_cover::<Vec<usize>>(&[], 3);
+        assert!(matching.is_empty() && cover.is_empty());
+    }
+
+    #[quickcheck]
+    fn cover_proves_matching_maximum(edges: Vec<(u8, u8)>) -> bool {
+        let mut neighbors = vec![vec![]; 8];
+        for (a, b) in edges {
+            let (a, b) = (a as usize % 8, b as usize % 5);
+            if !neighbors[a].contains(&b) {
+                neighbors[a].push(b);
+            }
+        }
+        let (matching, cover) = minimum_vertex_cover(&neighbors, 5);
+        is_valid(&neighbors, &matching)
+            && covers(&neighbors, &cover)
+            && cover.len() == matching.len()
+    }
+}
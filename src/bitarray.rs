@@ -83,6 +83,32 @@ impl<const C: usize> BitArray<C> {
         let width = std::mem::size_of::<usize>() * 8;
         self.0[index / width] |= 1 << (index % width);
     }
+
+    /// The words holding the bits, lowest bits first.
+    pub fn words(&self) -> [usize; C] {
+        self.0
+    }
+
+    pub fn from_words(words: [usize; C]) -> Self {
+        Self(words)
+    }
+
+    /// Iterates over the indices of the bits that are set.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let width = std::mem::size_of::<usize>() * 8;
+        (0..C * width).filter(move |i| self.get(*i))
+    }
+
+    /// Returns the indices of the `labels` that share at least one element with this set.
+    pub fn existential<const C2: usize>(&self, labels: &[BitArray<C>]) -> BitArray<C2> {
+        let mut out = zero();
+        for (i, label) in labels.iter().enumerate() {
+            if *label & *self != zero() {
+                out.set(i);
+            }
+        }
+        out
+    }
 }
 
 impl<const C: usize> Eq for BitArray<C> {}
@@ -110,10 +136,31 @@ impl<const C: usize> Ord for BitArray<C> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use quickcheck::*;
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::*;
     impl Arbitrary for BitArray<1> {
         fn arbitrary(g: &mut Gen) -> Self {
             Self([usize::arbitrary(g); 1])
         }
     }
+
+    #[quickcheck]
+    fn existential_over_singletons(set: BitArray<1>) -> bool {
+        let singletons = (0..64)
+            .map(|i| {
+                let mut s = zero();
+                s.set(i);
+                s
+            })
+            .collect::<Vec<_>>();
+        set.existential(&singletons) == set
+    }
+
+    #[test]
+    fn bits_beyond_first_cell() {
+        let mut set: BitArray<2> = zero();
+        set.set(70);
+        assert!(set.get(70) && !set.get(6));
+        assert_eq!(set.size(), 1);
+    }
 }
@@ -0,0 +1,162 @@
+//! A reference implementation of [`active_side`](crate::active_side) to test it against.
+//! It tries every line over the labels of the passive constraint and checks
+//! every configuration of it, so it is only usable for a handful of labels.
+
+use crate::bitarray::{zero, BitArray};
+use crate::line::Line;
+use itertools::Itertools;
+
+/// Returns the maximal lines all of whose configurations are allowed by `passive`.
+pub fn active_side<const C: usize, const D: usize>(passive: &[Line<C, D>]) -> Vec<Line<C, D>> {
+    let labels = passive
+        .iter()
+        .flat_map(|line| line.0.iter())
+        .fold(zero::<C>(), |a, b| a | *b)
+        .iter()
+        .collect::<Vec<_>>();
+    let sets = (1..1usize << labels.len())
+        .map(|mask| {
+            let mut set = zero();
+            for (i, label) in labels.iter().enumerate() {
+                if mask >> i & 1 == 1 {
+                    set.set(*label);
+                }
+            }
+            set
+        })
+        .collect::<Vec<BitArray<C>>>();
+
+    let allowed = |configuration: &[usize]| {
+        passive.iter().any(|line| {
+            (0..D)
+                .permutations(D)
+                .any(|p| (0..D).all(|i| line.0[p[i]].get(configuration[i])))
+        })
+    };
+
+    // Lines are unordered, so it is enough to try the sorted tuples of sets
+    let good = (0..sets.len())
+        .combinations_with_replacement(D)
+        .map(|indices| {
+            let mut line = [zero(); D];
+            for (set, i) in line.iter_mut().zip(indices) {
+                *set = sets[i];
+            }
+            line
+        })
+        .filter(|line| {
+            line.iter()
+                .map(|set| set.iter().collect::<Vec<_>>())
+                .multi_cartesian_product()
+                .all(|configuration| allowed(&configuration))
+        })
+        .collect::<Vec<_>>();
+
+    good.iter()
+        .enumerate()
+        .filter(|(i, line)| {
+            !good
+                .iter()
+                .enumerate()
+                .any(|(j, other)| *i != j && is_contained(line, other))
+        })
+        .map(|(_, line)| Line(*line))
+        .collect()
+}
+
+/// Returns whether the sets of `a` are subsets of the sets of `b` in some order.
+fn is_contained<const C: usize, const D: usize>(
+    a: &[BitArray<C>; D],
+    b: &[BitArray<C>; D],
+) -> bool {
+    (0..D)
+        .permutations(D)
+        .any(|p| (0..D).all(|i| a[i] & b[p[i]] == a[i]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::*;
+
+    const LABELS: usize = 3;
+
+    /// A few lines with nonempty sets over `LABELS` labels.
+    #[derive(Clone, Debug)]
+    struct Passive<const D: usize>(Vec<Line<1, D>>);
+
+    impl<const D: usize> Arbitrary for Passive<D> {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let lines = 1 + usize::arbitrary(g) % 3;
+            Self(
+                (0..lines)
+                    .map(|_| {
+                        let mut sets = [zero(); D];
+                        for set in sets.iter_mut() {
+                            let mask = 1 + usize::arbitrary(g) % ((1 << LABELS) - 1);
+                            *set = BitArray::from_words([mask]);
+                        }
+                        Line(sets)
+                    })
+                    .collect(),
+            )
+        }
+
+        /// Removes a line or a label from a set.
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            let mut smaller = vec![];
+            if self.0.len() > 1 {
+                for i in 0..self.0.len() {
+                    let mut lines = self.0.clone();
+                    lines.remove(i);
+                    smaller.push(Passive(lines));
+                }
+            }
+            for i in 0..self.0.len() {
+                for j in 0..D {
+                    let set = self.0[i].0[j];
+                    if set.size() > 1 {
+                        for label in set.iter() {
+                            let mut lines = self.0.clone();
+                            lines[i].0[j] = set ^ BitArray::from_words([1 << label]);
+                            smaller.push(Passive(lines));
+                        }
+                    }
+                }
+            }
+            Box::new(smaller.into_iter())
+        }
+    }
+
+    fn agrees<const D: usize>(passive: Vec<Line<1, D>>) -> bool
+    where
+        [(); D - 1]: Sized,
+    {
+        let expected = active_side(&passive);
+        let found = crate::active_side(passive);
+        found.len() == expected.len() && expected.iter().all(|x| found.contains(x))
+    }
+
+    #[test]
+    fn three_coloring() {
+        let passive = vec![line([&[0], &[1, 2]]), line([&[1], &[2]])];
+        let maximal = active_side(&passive);
+        assert_eq!(maximal.len(), 3);
+        assert!(maximal.contains(&line([&[0], &[1, 2]])));
+        assert!(maximal.contains(&line([&[0, 1], &[2]])));
+        assert!(maximal.contains(&line([&[0, 2], &[1]])));
+        assert!(agrees(passive));
+    }
+
+    #[quickcheck]
+    fn same_as_active_side_2(passive: Passive<2>) -> bool {
+        agrees(passive.0)
+    }
+
+    #[quickcheck]
+    fn same_as_active_side_3(passive: Passive<3>) -> bool {
+        agrees(passive.0)
+    }
+}
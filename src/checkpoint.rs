@@ -0,0 +1,247 @@
+//! Saving the state of long searches to files so that they can be resumed.
+//!
+//! The format is plain text. A header gives the number of cells and the degree.
+//! Then each of `input`, `todo`, `done` and `useless` follows as a line with its name and length
+//! and one line per entry. Sets are written as hexadecimal words separated by dots.
+//!
+//! `input` holds the lines the search started from, so that a checkpoint
+//! is only resumed with the input it was made for.
+
+use crate::bitarray::{zero, BitArray};
+use crate::line::Line;
+use crate::line_superiority::Matching;
+use crate::useless::UselessCache;
+use crate::SearchState;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, prelude::*, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+const HEADER: &str = "maximizer checkpoint";
+
+/// Writes the state of a search that started from the lines in `input`.
+pub fn write<const C: usize, const D: usize, M>(
+    state: &SearchState<C, D, M>,
+    input: &[Line<C, D>],
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "{} {} {}", HEADER, C, D)?;
+    writeln!(out, "input {}", input.len())?;
+    for line in input {
+        write_sets(&line.0, out)?;
+    }
+    writeln!(out, "todo {}", state.todo.len())?;
+    for line in &state.todo {
+        write_sets(&line.0, out)?;
+    }
+    writeln!(out, "done {}", state.done.len())?;
+    for line in &state.done {
+        write_sets(&line.0, out)?;
+    }
+    writeln!(out, "useless {}", state.useless.len())?;
+    for sets in state.useless.iter() {
+        write_sets(sets, out)?;
+    }
+    Ok(())
+}
+
+fn write_sets<const C: usize>(sets: &[BitArray<C>], out: &mut impl Write) -> io::Result<()> {
+    let sets = sets
+        .iter()
+        .map(|set| {
+            set.words()
+                .iter()
+                .map(|w| format!("{:x}", w))
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect::<Vec<_>>();
+    writeln!(out, "{}", sets.join(" "))
+}
+
+/// Reads a state written by [`write`]. Fails if it wasn't written for a search
+/// that started from the lines in `input`.
+pub fn read<const C: usize, const D: usize>(
+    input: &[Line<C, D>],
+    reader: impl BufRead,
+) -> Result<SearchState<C, D>, String> {
+    let mut lines = reader.lines();
+    let mut next_line = || {
+        lines
+            .next()
+            .unwrap_or_else(|| Err(io::ErrorKind::UnexpectedEof.into()))
+            .map_err(|e| e.to_string())
+    };
+
+    let header = next_line()?;
+    if header != format!("{} {} {}", HEADER, C, D) {
+        return Err(format!(
+            "Expected '{} {} {}' but found '{}'",
+            HEADER, C, D, header
+        ));
+    }
+
+    let mut section = |name: &str| -> Result<Vec<[BitArray<C>; D]>, String> {
+        let start = next_line()?;
+        let count = start
+            .strip_prefix(name)
+            .and_then(|x| x.trim().parse::<usize>().ok())
+            .ok_or_else(|| format!("Expected '{} <count>' but found '{}'", name, start))?;
+        (0..count).map(|_| read_sets(&next_line()?)).collect()
+    };
+
+    let saved_input = section("input")?;
+    if !saved_input.iter().eq(input.iter().map(|line| &line.0)) {
+        return Err("The checkpoint was made for a different input".to_string());
+    }
+    let todo = section("todo")?.into_iter().map(Line).collect();
+    let done = section("done")?.into_iter().map(Line).collect();
+    // The mode of the cache and the matcher aren't saved, so they are the defaults until changed.
+    let mut useless = UselessCache::default();
+    useless.extend(section("useless")?);
+    Ok(SearchState {
+        todo,
+        done,
+        useless,
+        matcher: Matching::default(),
+    })
+}
+
+fn read_sets<const C: usize, const D: usize>(line: &str) -> Result<[BitArray<C>; D], String> {
+    let invalid = || format!("Invalid line '{}'", line);
+
+    let mut sets = [zero(); D];
+    let mut parts = line.split_ascii_whitespace();
+    for set in sets.iter_mut() {
+        let words = parts
+            .next()
+            .ok_or_else(invalid)?
+            .split('.')
+            .map(|w| usize::from_str_radix(w, 16).map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        *set = BitArray::from_words(words.try_into().map_err(|_| invalid())?);
+    }
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok(sets)
+}
+
+/// Writes the state to `path` via a temporary file, so that an interrupted write
+/// doesn't destroy the previous checkpoint.
+pub fn save<const C: usize, const D: usize, M>(
+    state: &SearchState<C, D, M>,
+    input: &[Line<C, D>],
+    path: &Path,
+) -> io::Result<()> {
+    let temporary = temporary_path(path);
+    {
+        let mut out = BufWriter::new(File::create(&temporary)?);
+        write(state, input, &mut out)?;
+        out.flush()?;
+    }
+    std::fs::rename(temporary, path)
+}
+
+pub fn load<const C: usize, const D: usize>(
+    input: &[Line<C, D>],
+    path: &Path,
+) -> Result<SearchState<C, D>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    read(input, BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Appends `.tmp` to the file name, so that it differs from `path` whatever its extension.
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".tmp");
+    name.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::Limits;
+    use crate::test_util::wide_line as line;
+    use crate::{active_side, resume_active_side};
+
+    #[test]
+    fn resuming_gives_the_same_result() {
+        let passive = vec![
+            line([&[0], &[1, 2], &[100]]),
+            line([&[1], &[2], &[0, 100]]),
+            line([&[2], &[0, 1], &[0]]),
+        ];
+        let expected = active_side(passive.clone());
+
+        let mut saved = vec![];
+        resume_active_side(
+            &mut SearchState::new(passive.clone()),
+            &Limits::default(),
+            |_| {},
+            |state| {
+                let mut out = vec![];
+                write(state, &passive, &mut out).unwrap();
+                saved.push(out);
+            },
+        );
+        assert!(saved.len() > 1);
+
+        for text in saved {
+            let mut state = read::<2, 3>(&passive, &text[..]).unwrap();
+            resume_active_side(&mut state, &Limits::default(), |_| {}, |_| {});
+            assert_eq!(state.done, expected);
+        }
+    }
+
+    #[test]
+    fn limited_search_can_be_resumed() {
+        let passive = vec![
+            line([&[0], &[1, 2], &[100]]),
+            line([&[1], &[2], &[0, 100]]),
+            line([&[2], &[0, 1], &[0]]),
+        ];
+        let expected = active_side(passive.clone());
+
+        let limits = Limits {
+            combinations: Some(0),
+            ..Limits::default()
+        };
+        let mut state = SearchState::new(passive.clone());
+        let mut stops = 0;
+        while !resume_active_side(&mut state, &limits, |_| {}, |_| {}).is_complete() {
+            let mut out = vec![];
+            write(&state, &passive, &mut out).unwrap();
+            state = read::<2, 3>(&passive, &out[..]).unwrap();
+            stops += 1;
+        }
+        assert!(stops > 1);
+        assert_eq!(state.done, expected);
+    }
+
+    #[test]
+    fn wrong_size_is_rejected() {
+        let passive = vec![line::<2, 2>([&[0], &[1]])];
+        let mut out = vec![];
+        write(&SearchState::new(passive.clone()), &passive, &mut out).unwrap();
+        assert!(read::<2, 3>(&[], &out[..]).is_err());
+        assert!(read::<1, 2>(&[], &out[..]).is_err());
+        assert!(read::<2, 2>(&passive, &out[..]).is_ok());
+    }
+
+    #[test]
+    fn other_input_is_rejected() {
+        let passive = vec![line::<2, 2>([&[0], &[1]])];
+        let mut out = vec![];
+        write(&SearchState::new(passive.clone()), &passive, &mut out).unwrap();
+        assert!(read::<2, 2>(&[line([&[0], &[1, 2]])], &out[..]).is_err());
+        assert!(read::<2, 2>(&[], &out[..]).is_err());
+    }
+
+    #[test]
+    fn temporary_file_differs_from_checkpoint() {
+        for name in ["state", "state.txt", "state.tmp"] {
+            assert_ne!(temporary_path(Path::new(name)), Path::new(name));
+        }
+    }
+}
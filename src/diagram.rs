@@ -0,0 +1,141 @@
+//! Diagrams of how strong labels are relative to each other.
+//!
+//! A label `A` is at least as strong as `B` if replacing `B` by `A` in any allowed
+//! configuration keeps it allowed. For maximal lines this is the case exactly if
+//! every set that contains `B` also contains `A`.
+
+use crate::bitarray::{zero, BitArray};
+use crate::labels::LabelTable;
+use crate::line::Line;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagram<const C: usize> {
+    /// `stronger[b]` has every label that is at least as strong as `b`, except `b` itself.
+    pub stronger: Vec<BitArray<C>>,
+}
+
+impl<const C: usize> Diagram<C> {
+    /// Computes the diagram of a constraint over labels `0..labels`.
+    /// The lines should be maximal, for example the result of [`crate::active_side`].
+    pub fn new<const D: usize>(lines: &[Line<C, D>], labels: usize) -> Self {
+        let mut alphabet = zero();
+        for i in 0..labels {
+            alphabet.set(i);
+        }
+
+        let mut stronger = vec![alphabet; labels];
+        for set in lines.iter().flat_map(|l| l.0.iter()) {
+            for b in set.iter().filter(|b| *b < labels) {
+                stronger[b] = stronger[b] & *set;
+            }
+        }
+        for (b, s) in stronger.iter_mut().enumerate() {
+            *s = *s ^ single(b);
+        }
+
+        Self { stronger }
+    }
+
+    pub fn is_at_least_as_strong(&self, a: usize, b: usize) -> bool {
+        a == b || self.stronger[b].get(a)
+    }
+
+    /// Returns the transitive reduction: `reduced[b]` has the labels stronger than `b`
+    /// that aren't implied by going through a third label.
+    /// Equivalent labels are connected in both directions.
+    pub fn reduced(&self) -> Vec<BitArray<C>> {
+        let equivalent = |a: usize, b: usize| self.stronger[a].get(b) && self.stronger[b].get(a);
+        self.stronger
+            .iter()
+            .enumerate()
+            .map(|(b, above)| {
+                let mut out = zero();
+                for a in above.iter() {
+                    let implied = above
+                        .iter()
+                        .filter(|c| !equivalent(*c, a) && !equivalent(*c, b))
+                        .any(|c| self.stronger[c].get(a));
+                    if !implied {
+                        out.set(a);
+                    }
+                }
+                out
+            })
+            .collect()
+    }
+
+    /// Writes the transitive reduction with one line per label,
+    /// listing the labels directly above it, like `A: B C`.
+    pub fn to_text(&self, names: &LabelTable) -> String {
+        self.reduced()
+            .iter()
+            .enumerate()
+            .map(|(b, above)| {
+                let mut line = format!("{}:", names.name(b));
+                for a in above.iter() {
+                    line.push(' ');
+                    line.push_str(names.name(a));
+                }
+                line + "\n"
+            })
+            .collect()
+    }
+
+    /// Writes the transitive reduction in Graphviz format with edges pointing to stronger labels.
+    pub fn to_dot(&self, names: &LabelTable) -> String {
+        let mut out = "digraph {\n".to_owned();
+        for b in 0..self.stronger.len() {
+            out += &format!("    {:?};\n", names.name(b));
+        }
+        for (b, above) in self.reduced().iter().enumerate() {
+            for a in above.iter() {
+                out += &format!("    {:?} -> {:?};\n", names.name(b), names.name(a));
+            }
+        }
+        out + "}\n"
+    }
+}
+
+fn single<const C: usize>(label: usize) -> BitArray<C> {
+    let mut out = zero();
+    out.set(label);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+
+    #[test]
+    fn chain_is_reduced() {
+        let diagram = Diagram::new(&[line([&[0, 1, 2], &[1, 2], &[2]])], 3);
+        assert!(diagram.is_at_least_as_strong(2, 0));
+        assert!(!diagram.is_at_least_as_strong(0, 2));
+        assert_eq!(diagram.reduced(), vec![single(1), single(2), zero()]);
+
+        let names = LabelTable::fresh(3);
+        assert_eq!(diagram.to_text(&names), "A: B\nB: C\nC:\n");
+        assert_eq!(
+            diagram.to_dot(&names),
+            "digraph {\n    \"A\";\n    \"B\";\n    \"C\";\n    \
+            \"A\" -> \"B\";\n    \"B\" -> \"C\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn equivalent_labels() {
+        // 0 and 1 always occur together and 2 is only allowed where they are.
+        let diagram = Diagram::new(&[line([&[0, 1, 2], &[0, 1]])], 3);
+        assert_eq!(
+            diagram.reduced(),
+            vec![single(1), single(0), single(0) | single(1)]
+        );
+    }
+
+    #[test]
+    fn incomparable_labels() {
+        let diagram = Diagram::new(&[line([&[0, 1], &[2]]), line([&[1], &[1, 2]])], 3);
+        assert_eq!(diagram.reduced(), vec![single(1), zero(), zero()]);
+    }
+}
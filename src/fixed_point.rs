@@ -0,0 +1,108 @@
+use crate::isomorphism::{canonical_form, renaming_between};
+use crate::{active_side, labels_of, maximize, next_problem, Problem};
+
+/// A sequence of problems, each obtained from the previous one by two round elimination steps,
+/// whose last problem is the same as an earlier one up to renaming labels.
+#[derive(Clone, Debug)]
+pub struct Cycle<const C: usize, const A: usize, const P: usize> {
+    pub problems: Vec<Problem<C, A, P>>,
+    /// The index of the problem that the last one is isomorphic to.
+    pub start: usize,
+    /// Label `i` of the last problem corresponds to label `renaming[i]` of the start.
+    pub renaming: Vec<usize>,
+}
+
+/// Eliminates rounds until the problem is isomorphic to one seen before.
+/// Both constraints are maximized after every step, so each problem is in a unique form.
+///
+/// Gives up after `max_steps` pairs of steps or when the labels don't fit into `C` cells anymore.
+/// In that case the problems computed so far are returned.
+pub fn find_fixed_point<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    max_steps: usize,
+) -> Result<Cycle<C, A, P>, Vec<Problem<C, A, P>>>
+where
+    [(); A - 1]: Sized,
+    [(); P - 1]: Sized,
+{
+    let mut problems = vec![maximize(problem)];
+    let mut canonical = vec![canonical_form(&problems[0])];
+
+    for _ in 0..max_steps {
+        // SAFETY: there is at least the original problem
+        let half = match step(problems.last().unwrap()) {
+            Some(x) => maximize(&x),
+            None => break,
+        };
+        let next = match step(&half) {
+            Some(x) => maximize(&x),
+            None => break,
+        };
+
+        let (form, renaming) = canonical_form(&next);
+        for (start, (old_form, old_renaming)) in canonical.iter().enumerate() {
+            if form.active == old_form.active && form.passive == old_form.passive {
+                problems.push(next);
+                return Ok(Cycle {
+                    problems,
+                    start,
+                    renaming: renaming_between(renaming, old_renaming.clone()),
+                });
+            }
+        }
+        problems.push(next);
+        canonical.push((form, renaming));
+    }
+
+    Err(problems)
+}
+
+/// Like [`crate::round_eliminate`] but returns `None` if the new labels don't fit.
+fn step<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+) -> Option<Problem<C, P, A>>
+where
+    [(); P - 1]: Sized,
+{
+    let maximal = active_side(problem.passive.clone());
+    let labels = labels_of(&maximal);
+    if crate::bitarray::cells_needed(labels.len()) > C {
+        return None;
+    }
+    Some(next_problem(&maximal, &problem.active, &labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+
+    #[test]
+    fn sinkless_orientation_is_a_fixed_point() {
+        // 0 is outgoing and 1 incoming
+        let problem = Problem {
+            active: vec![line([&[0], &[0, 1], &[0, 1]])],
+            passive: vec![line([&[0], &[1]])],
+        };
+        let cycle = find_fixed_point(&problem, 3).unwrap();
+
+        // The input itself is not reproduced, but the problem after it is.
+        assert_eq!(cycle.start, 1);
+        assert_eq!(cycle.problems.len(), 3);
+    }
+
+    #[test]
+    fn labels_need_not_start_at_zero() {
+        // Both problems only use one label, but the input calls it 5 while
+        // round elimination calls it 0.
+        for label in [0, 5] {
+            let problem = Problem::<1, 2, 2> {
+                active: vec![line([&[label], &[label]])],
+                passive: vec![line([&[label], &[label]])],
+            };
+            let cycle = find_fixed_point(&problem, 1).unwrap();
+            assert_eq!(cycle.start, 0);
+            assert_eq!(cycle.renaming[0], label);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Hardening, the opposite of relaxation: forbidding labels to get a smaller subproblem.
+//!
+//! Removing labels from maximal lines and dropping the lines that become empty or
+//! inferior to others gives the maximal lines of the restricted constraint again,
+//! so nothing has to be recomputed with [`crate::active_side`].
+
+use crate::bitarray::{zero, BitArray};
+use crate::line::Line;
+use crate::line_superiority::{is_inferior_to_with, Matcher, Matching};
+use crate::relax::map_sets;
+use crate::Problem;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Active,
+    Passive,
+}
+
+/// Forbids the labels in `removed` in a constraint given by its maximal lines,
+/// such as the result of [`crate::active_side`].
+pub fn harden_lines<const C: usize, const D: usize>(
+    lines: &[Line<C, D>],
+    removed: BitArray<C>,
+) -> Vec<Line<C, D>> {
+    harden_lines_with(lines, removed, &Matching::default())
+}
+
+/// Like [`harden_lines`] but compares lines with `matcher`.
+pub fn harden_lines_with<const C: usize, const D: usize>(
+    lines: &[Line<C, D>],
+    removed: BitArray<C>,
+    matcher: &impl Matcher,
+) -> Vec<Line<C, D>> {
+    let restricted = map_sets(lines, |set| set & !removed);
+    restricted
+        .iter()
+        .filter(|l| {
+            !restricted
+                .iter()
+                .any(|x| x != *l && is_inferior_to_with(matcher, *l, x))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Forbids the labels in `removed` on one side of a problem whose constraints are maximal.
+///
+/// Labels that no longer occur on one side are useless on the other side as well,
+/// so they are removed there too until both sides use the same labels.
+pub fn harden<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    removed: BitArray<C>,
+    side: Side,
+) -> Problem<C, A, P> {
+    let mut active = problem.active.clone();
+    let mut passive = problem.passive.clone();
+    let (mut from_active, mut from_passive) = match side {
+        Side::Active => (removed, zero()),
+        Side::Passive => (zero(), removed),
+    };
+
+    while from_active != zero() || from_passive != zero() {
+        active = harden_lines(&active, from_active);
+        passive = harden_lines(&passive, from_passive);
+
+        let in_active = used(&active);
+        let in_passive = used(&passive);
+        from_active = in_active & !in_passive;
+        from_passive = in_passive & !in_active;
+    }
+
+    Problem { active, passive }
+}
+
+fn used<const C: usize, const D: usize>(lines: &[Line<C, D>]) -> BitArray<C> {
+    lines
+        .iter()
+        .flat_map(|l| l.0.iter())
+        .fold(zero(), |a, b| a | *b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+
+    #[test]
+    fn inferior_lines_are_dropped() {
+        let lines = [line([&[0, 1], &[2]]), line([&[0], &[1, 2]])];
+        assert_eq!(
+            harden_lines(&lines, line([&[1]]).0[0]),
+            vec![line([&[0], &[2]])]
+        );
+    }
+
+    #[test]
+    fn removal_propagates() {
+        // Forbidding 2 on the passive side leaves no passive line with 0,
+        // so the active lines using 0 or 2 can't be used anymore.
+        let problem = Problem {
+            active: vec![line([&[0], &[1]]), line([&[1], &[1]]), line([&[2], &[2]])],
+            passive: vec![line([&[0], &[2]]), line([&[1, 2], &[1, 2]])],
+        };
+        let problem = harden(&problem, line([&[2]]).0[0], Side::Passive);
+        assert_eq!(problem.active, vec![line([&[1], &[1]])]);
+        assert_eq!(problem.passive, vec![line([&[1], &[1]])]);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::bitarray::{zero, BitArray};
+use crate::line::Line;
+use crate::line_superiority::{is_inferior_to_with, Matcher, Matching};
+use std::collections::BTreeMap;
+
+/// A set of lines that answers which of them dominate a line or are dominated by it.
+///
+/// A line can only be superior to another one if it allows every label of the other.
+/// The lines are stored in a trie over the sorted labels of the union of their sets,
+/// so a query only descends into the branches whose unions contain, or are contained in,
+/// the union of the query. Other lines are never looked at. Of the lines found, only those
+/// of a suitable [`Line::size`] are compared with the full check.
+#[derive(Clone, Debug, Default)]
+pub struct LineIndex<const C: usize, const D: usize, M = Matching> {
+    root: Node<C, D>,
+    /// Used for the full check.
+    matcher: M,
+}
+
+/// Holds the lines whose union consists of the labels on the path from the root to it.
+#[derive(Clone, Debug, Default)]
+struct Node<const C: usize, const D: usize> {
+    lines: Vec<Line<C, D>>,
+    children: BTreeMap<usize, Node<C, D>>,
+}
+
+impl<const C: usize, const D: usize, M: Matcher> LineIndex<C, D, M> {
+    pub fn new<'a>(lines: impl IntoIterator<Item = &'a Line<C, D>>, matcher: M) -> Self {
+        let mut out = Self {
+            root: Node::default(),
+            matcher,
+        };
+        for line in lines {
+            out.insert(line.clone());
+        }
+        out
+    }
+
+    pub fn insert(&mut self, line: Line<C, D>) {
+        let mut node = &mut self.root;
+        for label in union(&line).iter() {
+            node = node.children.entry(label).or_default();
+        }
+        node.lines.push(line);
+    }
+
+    /// Removes one line equal to `line`. Returns whether there was one.
+    pub fn remove(&mut self, line: &Line<C, D>) -> bool {
+        self.root.remove(&labels(line), line)
+    }
+
+    /// Returns whether some line `x` in the index satisfies `x >= line`.
+    pub fn is_dominated(&self, line: &Line<C, D>) -> bool {
+        let size = line.size();
+        self.root.any_superset(&labels(line), &mut |x| {
+            x.size() >= size && is_inferior_to_with(&self.matcher, line, x)
+        })
+    }
+
+    /// Returns every line in the index that is inferior to `line`.
+    pub fn inferior_to(&self, line: &Line<C, D>) -> Vec<&Line<C, D>> {
+        let size = line.size();
+        let mut out = vec![];
+        self.root.subsets(
+            &labels(line),
+            &|x| x.size() <= size && is_inferior_to_with(&self.matcher, x, line),
+            &mut out,
+        );
+        out
+    }
+
+    /// Removes every line in the index that is inferior to `line` and returns them.
+    pub fn remove_inferior_to(&mut self, line: &Line<C, D>) -> Vec<Line<C, D>> {
+        let size = line.size();
+        let matcher = &self.matcher;
+        let mut out = vec![];
+        self.root.remove_subsets(
+            &labels(line),
+            &|x| x.size() <= size && is_inferior_to_with(matcher, x, line),
+            &mut out,
+        );
+        out
+    }
+}
+
+impl<const C: usize, const D: usize> Node<C, D> {
+    fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.children.is_empty()
+    }
+
+    /// Removes a line equal to `line` from the node at the end of `path`.
+    /// Nodes left without lines below them are removed as well.
+    fn remove(&mut self, path: &[usize], line: &Line<C, D>) -> bool {
+        match path.split_first() {
+            None => match self.lines.iter().position(|x| x == line) {
+                Some(i) => {
+                    self.lines.swap_remove(i);
+                    true
+                }
+                None => false,
+            },
+            Some((label, rest)) => match self.children.get_mut(label) {
+                Some(child) => {
+                    let found = child.remove(rest, line);
+                    if child.is_empty() {
+                        self.children.remove(label);
+                    }
+                    found
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Returns whether `f` holds for a line below this node whose union has
+    /// all labels in `needed`, which are sorted and larger than those on the path here.
+    fn any_superset(&self, needed: &[usize], f: &mut impl FnMut(&Line<C, D>) -> bool) -> bool {
+        let children = match needed.first() {
+            None => {
+                if self.lines.iter().any(&mut *f) {
+                    return true;
+                }
+                self.children.range(..)
+            }
+            // A branch past the next needed label can't have it
+            Some(next) => self.children.range(..=*next),
+        };
+        for (label, child) in children {
+            let rest = match needed.split_first() {
+                Some((next, rest)) if next == label => rest,
+                _ => needed,
+            };
+            if child.any_superset(rest, f) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Adds the lines below this node for which `keep` holds to `out`, if their unions
+    /// only have labels on the path here or in `allowed`, which is sorted.
+    fn subsets<'a>(
+        &'a self,
+        allowed: &[usize],
+        keep: &impl Fn(&Line<C, D>) -> bool,
+        out: &mut Vec<&'a Line<C, D>>,
+    ) {
+        out.extend(self.lines.iter().filter(|x| keep(x)));
+        for (i, label) in allowed.iter().enumerate() {
+            if let Some(child) = self.children.get(label) {
+                child.subsets(&allowed[i + 1..], keep, out);
+            }
+        }
+    }
+
+    /// Like [`Node::subsets`] but removes the lines.
+    fn remove_subsets(
+        &mut self,
+        allowed: &[usize],
+        keep: &impl Fn(&Line<C, D>) -> bool,
+        out: &mut Vec<Line<C, D>>,
+    ) {
+        let mut i = 0;
+        while i < self.lines.len() {
+            if keep(&self.lines[i]) {
+                out.push(self.lines.swap_remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        for (i, label) in allowed.iter().enumerate() {
+            if let Some(child) = self.children.get_mut(label) {
+                child.remove_subsets(&allowed[i + 1..], keep, out);
+                if child.is_empty() {
+                    self.children.remove(label);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the labels of the union of the sets of `line` in ascending order.
+fn labels<const C: usize, const D: usize>(line: &Line<C, D>) -> Vec<usize> {
+    union(line).iter().collect()
+}
+
+fn union<const C: usize, const D: usize>(line: &Line<C, D>) -> BitArray<C> {
+    line.0.iter().fold(zero(), |a, b| a | *b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_superiority::is_inferior_to;
+    use crate::relax::map_sets;
+    use quickcheck_macros::*;
+
+    #[quickcheck]
+    fn same_answers_as_scanning(lines: Vec<Line<1, 3>>, queries: Vec<Line<1, 3>>) -> bool {
+        same_answers(&lines, &queries)
+    }
+
+    /// With few labels, many unions are contained in one another.
+    #[quickcheck]
+    fn same_answers_on_few_labels(lines: Vec<Line<1, 3>>, queries: Vec<Line<1, 3>>) -> bool {
+        let few =
+            |lines: Vec<Line<1, 3>>| map_sets(&lines, |set| set & BitArray::from_words([0b1111]));
+        same_answers(&few(lines), &few(queries))
+    }
+
+    fn same_answers(lines: &[Line<1, 3>], queries: &[Line<1, 3>]) -> bool {
+        let index = LineIndex::new(lines, Matching::default());
+        queries.iter().all(|q| {
+            let inferior = lines
+                .iter()
+                .filter(|x| is_inferior_to(x, q))
+                .collect::<Vec<_>>();
+            let found = index.inferior_to(q);
+            index.is_dominated(q) == lines.iter().any(|x| x >= q)
+                && found.len() == inferior.len()
+                && found.iter().all(|x| inferior.contains(x))
+        })
+    }
+
+    #[quickcheck]
+    fn removes_the_inferior_lines(lines: Vec<Line<1, 3>>, query: Line<1, 3>) -> bool {
+        let mut index = LineIndex::new(&lines, Matching::default());
+        let inferior = index
+            .inferior_to(&query)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let removed = index.remove_inferior_to(&query);
+        removed.len() == inferior.len()
+            && removed.iter().all(|x| inferior.contains(x))
+            && index.inferior_to(&query).is_empty()
+    }
+
+    #[quickcheck]
+    fn removed_lines_are_gone(lines: Vec<Line<1, 3>>) -> bool {
+        let mut index = LineIndex::new(&lines, Matching::default());
+        lines.iter().all(|x| index.remove(x)) && index.root.is_empty()
+    }
+}
@@ -0,0 +1,585 @@
+//! Canonical forms of problems with respect to renaming labels.
+//!
+//! Labels are first told apart by how they occur in the constraints. Whenever that
+//! doesn't distinguish all of them, each label of the smallest ambiguous class is tried
+//! as the first one and the smallest resulting problem is kept.
+//!
+//! Two orderings that give the same problem reveal a renaming that maps the problem to itself.
+//! Such renamings are remembered, and a label isn't tried if one of them maps a label
+//! that was already tried to it while keeping the labels chosen before in place.
+
+use crate::bitarray::{zero, BitArray};
+use crate::line::Line;
+use crate::Problem;
+
+/// Renames the labels so that problems that are the same up to renaming become equal.
+///
+/// The alphabet is taken to be every label up to the largest one in use.
+/// Returns the renamed problem, whose lines are sorted, and the renaming,
+/// which maps every old label to its new label.
+///
+/// The form depends on how the constraints are written down,
+/// so both should be maximized if only the allowed configurations matter.
+pub fn canonical_form<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+) -> (Problem<C, A, P>, Vec<usize>) {
+    let used = problem
+        .active
+        .iter()
+        .flat_map(|l| l.0.iter())
+        .chain(problem.passive.iter().flat_map(|l| l.0.iter()))
+        .fold(zero(), |a: BitArray<C>, b| a | *b);
+    let labels = used.iter().collect::<Vec<_>>();
+    let alphabet_size = labels.last().map_or(0, |x| x + 1);
+
+    let places = labels
+        .iter()
+        .map(|l| (places(&problem.active, *l), places(&problem.passive, *l)))
+        .collect::<Vec<_>>();
+
+    // Labels that occur in exactly the same sets can be swapped without changing anything.
+    let mut found = Found {
+        first: None,
+        best: None,
+        automorphisms: vec![],
+    };
+    for i in 0..labels.len() {
+        if let Some(j) = (0..i).find(|j| places[*j] == places[i]) {
+            let mut swap = (0..labels.len()).collect::<Vec<_>>();
+            swap.swap(i, j);
+            found.automorphisms.push(swap);
+        }
+    }
+
+    search(
+        problem,
+        &labels,
+        &places,
+        vec![0; labels.len()],
+        &mut vec![],
+        &mut found,
+    );
+    // SAFETY: the search always reaches at least one ordering
+    let order = found.best.unwrap().order(&labels);
+
+    let mut renaming = vec![0; alphabet_size];
+    for (new, &old) in order.iter().enumerate() {
+        renaming[old] = new;
+    }
+    for (new, old) in (labels.len()..).zip((0..alphabet_size).filter(|x| !used.get(*x))) {
+        renaming[old] = new;
+    }
+
+    (rename(problem, &renaming), renaming)
+}
+
+/// Returns a bijection that maps the labels of `a` to the labels of `b` so that
+/// `a` turns into `b`, or `None` if there is no such bijection.
+/// The alphabet of both is every label up to the largest one used by either.
+pub fn are_isomorphic<const C: usize, const A: usize, const P: usize>(
+    a: &Problem<C, A, P>,
+    b: &Problem<C, A, P>,
+) -> Option<Vec<usize>> {
+    let (canonical_a, renaming_a) = canonical_form(a);
+    let (canonical_b, renaming_b) = canonical_form(b);
+    if encode(&canonical_a.active) != encode(&canonical_b.active)
+        || encode(&canonical_a.passive) != encode(&canonical_b.passive)
+    {
+        return None;
+    }
+
+    Some(renaming_between(renaming_a, renaming_b))
+}
+
+/// Given the renamings that [`canonical_form`] returned for two problems with the same
+/// canonical form, returns the bijection that maps the labels of the first one to the labels
+/// of the second one. The alphabet of both is every label up to the largest one used by either.
+pub fn renaming_between(mut renaming_a: Vec<usize>, mut renaming_b: Vec<usize>) -> Vec<usize> {
+    // Labels above the largest used one are unused, so they go last in their original order.
+    let alphabet_size = renaming_a.len().max(renaming_b.len());
+    for renaming in [&mut renaming_a, &mut renaming_b] {
+        renaming.extend(renaming.len()..alphabet_size);
+    }
+
+    let mut inverse_b = vec![0; alphabet_size];
+    for (old, &new) in renaming_b.iter().enumerate() {
+        inverse_b[new] = old;
+    }
+    renaming_a.iter().map(|x| inverse_b[*x]).collect()
+}
+
+/// Like [`canonical_form`] for a single constraint.
+pub fn canonical_lines<const C: usize, const D: usize>(
+    lines: &[Line<C, D>],
+) -> (Vec<Line<C, D>>, Vec<usize>) {
+    let (problem, renaming) = canonical_form::<C, D, D>(&Problem {
+        active: lines.to_vec(),
+        passive: vec![],
+    });
+    (problem.active, renaming)
+}
+
+type Encoding<const C: usize, const A: usize, const P: usize> =
+    (Vec<[BitArray<C>; A]>, Vec<[BitArray<C>; P]>);
+
+/// Sorts the sets in each line and the lines so that equal constraints compare equal.
+fn encode<const C: usize, const D: usize>(lines: &[Line<C, D>]) -> Vec<[BitArray<C>; D]> {
+    let mut out = lines
+        .iter()
+        .map(|l| {
+            let mut sets = l.0;
+            sets.sort();
+            sets
+        })
+        .collect::<Vec<_>>();
+    out.sort();
+    out
+}
+
+fn rename<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    renaming: &[usize],
+) -> Problem<C, A, P> {
+    Problem {
+        active: rename_lines(&problem.active, renaming),
+        passive: rename_lines(&problem.passive, renaming),
+    }
+}
+
+fn rename_lines<const C: usize, const D: usize>(
+    lines: &[Line<C, D>],
+    renaming: &[usize],
+) -> Vec<Line<C, D>> {
+    let mut out = lines
+        .iter()
+        .map(|line| {
+            let mut sets = [zero(); D];
+            for (new, old) in sets.iter_mut().zip(&line.0) {
+                for x in old.iter() {
+                    new.set(renaming[x]);
+                }
+            }
+            sets.sort();
+            sets
+        })
+        .collect::<Vec<_>>();
+    out.sort();
+    out.into_iter().map(Line).collect()
+}
+
+/// The active and passive lines and positions at which a label occurs.
+type Places = (Vec<(usize, usize)>, Vec<(usize, usize)>);
+
+/// Every line and position at which `label` occurs.
+fn places<const C: usize, const D: usize>(
+    lines: &[Line<C, D>],
+    label: usize,
+) -> Vec<(usize, usize)> {
+    let mut out = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        for (j, set) in line.0.iter().enumerate() {
+            if set.get(label) {
+                out.push((i, j));
+            }
+        }
+    }
+    out
+}
+
+/// An ordering of the labels reached by the search.
+#[derive(Clone)]
+struct Leaf<const C: usize, const A: usize, const P: usize> {
+    encoding: Encoding<C, A, P>,
+    /// `colors[i]` is the new position of `labels[i]`.
+    colors: Vec<usize>,
+    /// The labels individualized on the way, as indices of `labels`.
+    path: Vec<usize>,
+}
+
+impl<const C: usize, const A: usize, const P: usize> Leaf<C, A, P> {
+    /// The labels in their new order.
+    fn order(&self, labels: &[usize]) -> Vec<usize> {
+        let mut order = vec![0; labels.len()];
+        for (label, color) in labels.iter().zip(&self.colors) {
+            order[*color] = *label;
+        }
+        order
+    }
+}
+
+struct Found<const C: usize, const A: usize, const P: usize> {
+    first: Option<Leaf<C, A, P>>,
+    best: Option<Leaf<C, A, P>>,
+    /// Renamings that map the problem to itself, as permutations of the indices of `labels`.
+    automorphisms: Vec<Vec<usize>>,
+}
+
+/// `colors[i]` is the class of `labels[i]`. Classes are numbered by rank, so two labels
+/// with the same class are interchangeable as far as the refinement can tell.
+/// `path` holds the labels individualized so far.
+///
+/// Returns the depth to jump back to if the rest of the tree above this node
+/// is known to give nothing new.
+fn search<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    labels: &[usize],
+    places: &[Places],
+    colors: Vec<usize>,
+    path: &mut Vec<usize>,
+    found: &mut Found<C, A, P>,
+) -> Option<usize> {
+    let colors = refine(problem, labels, places, colors);
+
+    let mut counts = vec![0; labels.len()];
+    for c in &colors {
+        counts[*c] += 1;
+    }
+    match (0..labels.len()).find(|c| counts[*c] > 1) {
+        None => leaf(problem, labels, colors, path, found),
+        Some(cell) => {
+            let depth = path.len();
+            let mut tried: Vec<usize> = vec![];
+            for i in (0..labels.len()).filter(|i| colors[*i] == cell) {
+                // Skip labels that a known automorphism fixing the path maps a tried label to
+                let orbit = orbits(&found.automorphisms, path, labels.len());
+                if tried.iter().any(|t| orbit[*t] == orbit[i]) {
+                    continue;
+                }
+                tried.push(i);
+
+                let individualized = colors
+                    .iter()
+                    .enumerate()
+                    .map(|(j, c)| if j == i { 2 * c } else { 2 * c + 1 })
+                    .collect();
+                path.push(i);
+                let jump = search(problem, labels, places, individualized, path, found);
+                path.pop();
+                if let Some(target) = jump {
+                    if target < depth {
+                        return Some(target);
+                    }
+                }
+            }
+            None
+        }
+    }
+}
+
+/// Keeps the leaf if it is the first or the best one so far. If it gives the same problem
+/// as one of those, the automorphism between them is recorded, and the search jumps back
+/// to where the paths to the two leaves part: the automorphism maps the subtree
+/// explored before onto the current one, so the rest of the current one gives nothing new.
+fn leaf<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    labels: &[usize],
+    colors: Vec<usize>,
+    path: &[usize],
+    found: &mut Found<C, A, P>,
+) -> Option<usize> {
+    let mut renaming = vec![0; labels.last().map_or(0, |x| x + 1)];
+    for (label, color) in labels.iter().zip(&colors) {
+        renaming[*label] = *color;
+    }
+    let renamed = rename(problem, &renaming);
+    let leaf = Leaf {
+        encoding: (encode(&renamed.active), encode(&renamed.passive)),
+        colors,
+        path: path.to_vec(),
+    };
+
+    let same = found
+        .first
+        .iter()
+        .chain(&found.best)
+        .find(|known| known.encoding == leaf.encoding);
+    if let Some(known) = same {
+        // Maps the label at each position of the known ordering to the one at that position now
+        let mut at = vec![0; labels.len()];
+        for (i, color) in leaf.colors.iter().enumerate() {
+            at[*color] = i;
+        }
+        let automorphism = known.colors.iter().map(|c| at[*c]).collect();
+        let parting = known
+            .path
+            .iter()
+            .zip(path)
+            .take_while(|(a, b)| a == b)
+            .count();
+        found.automorphisms.push(automorphism);
+        return Some(parting);
+    }
+
+    if found
+        .best
+        .as_ref()
+        .is_none_or(|b| leaf.encoding < b.encoding)
+    {
+        if found.first.is_none() {
+            found.first = Some(leaf.clone());
+        }
+        found.best = Some(leaf);
+    }
+    None
+}
+
+/// Returns a representative for each label such that two labels have the same one
+/// if the automorphisms that keep the labels in `fixed` in place map one to the other.
+fn orbits(automorphisms: &[Vec<usize>], fixed: &[usize], len: usize) -> Vec<usize> {
+    let mut parent = (0..len).collect::<Vec<_>>();
+    fn root(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+    for a in automorphisms
+        .iter()
+        .filter(|a| fixed.iter().all(|x| a[*x] == *x))
+    {
+        for (x, y) in a.iter().enumerate() {
+            let (x, y) = (root(&mut parent, x), root(&mut parent, *y));
+            parent[x.max(y)] = x.min(y);
+        }
+    }
+    (0..len).map(|x| root(&mut parent, x)).collect()
+}
+
+/// Splits classes of labels that occur differently until no more classes split.
+fn refine<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    labels: &[usize],
+    places: &[Places],
+    mut colors: Vec<usize>,
+) -> Vec<usize> {
+    let mut color_of = vec![0; labels.last().map_or(0, |x| x + 1)];
+    let mut classes = usize::MAX;
+    loop {
+        for (label, color) in labels.iter().zip(&colors) {
+            color_of[*label] = *color;
+        }
+
+        // Sets are described by the classes in them and lines by the descriptions of their sets.
+        let active_sets = set_colors(&problem.active, &color_of);
+        let passive_sets = set_colors(&problem.passive, &color_of);
+        let set_ranks = ranks(active_sets.iter().chain(&passive_sets).flatten());
+        let active_sets = active_sets
+            .iter()
+            .map(|l| l.iter().map(&set_ranks).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let passive_sets = passive_sets
+            .iter()
+            .map(|l| l.iter().map(&set_ranks).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let sorted = |sets: &Vec<usize>| {
+            let mut out = sets.clone();
+            out.sort();
+            out
+        };
+        let active_lines = active_sets.iter().map(sorted).collect::<Vec<_>>();
+        let passive_lines = passive_sets.iter().map(sorted).collect::<Vec<_>>();
+        let line_ranks = ranks(active_lines.iter().chain(&passive_lines));
+
+        let signatures = places
+            .iter()
+            .zip(&colors)
+            .map(|((active, passive), &color)| {
+                let mut found = active
+                    .iter()
+                    .map(|&(l, i)| (0, active_sets[l][i], line_ranks(&active_lines[l])))
+                    .chain(
+                        passive
+                            .iter()
+                            .map(|&(l, i)| (1, passive_sets[l][i], line_ranks(&passive_lines[l]))),
+                    )
+                    .collect::<Vec<_>>();
+                found.sort();
+                (color, found)
+            })
+            .collect::<Vec<_>>();
+
+        let signature_ranks = ranks(signatures.iter());
+        colors = signatures.iter().map(&signature_ranks).collect();
+
+        let new_classes = colors.iter().max().map_or(0, |x| x + 1);
+        if new_classes == classes {
+            return colors;
+        }
+        classes = new_classes;
+    }
+}
+
+/// The sorted classes of the labels in each set of each line.
+fn set_colors<const C: usize, const D: usize>(
+    lines: &[Line<C, D>],
+    color_of: &[usize],
+) -> Vec<Vec<Vec<usize>>> {
+    lines
+        .iter()
+        .map(|line| {
+            line.0
+                .iter()
+                .map(|set| {
+                    let mut out = set.iter().map(|x| color_of[x]).collect::<Vec<_>>();
+                    out.sort();
+                    out
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Numbers the distinct items in sorted order and returns a function that looks up the numbers.
+fn ranks<'a, T: Ord + 'a>(items: impl Iterator<Item = &'a T>) -> impl Fn(&T) -> usize + 'a {
+    let mut sorted = items.collect::<Vec<_>>();
+    sorted.sort();
+    sorted.dedup();
+    // SAFETY: only called with the items given
+    move |x| sorted.binary_search(&x).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+    use quickcheck_macros::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn renaming_is_found() {
+        let a = Problem {
+            active: vec![line([&[0], &[1]])],
+            passive: vec![line([&[1], &[1]]), line([&[0], &[2]])],
+        };
+        let b = Problem {
+            active: vec![line([&[2], &[0]])],
+            passive: vec![line([&[0], &[0]]), line([&[1], &[2]])],
+        };
+        assert_eq!(are_isomorphic(&a, &b), Some(vec![2, 0, 1]));
+        assert_eq!(are_isomorphic(&a, &a), Some(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn symmetric_labels() {
+        // 3-coloring on cycles, written down in two ways
+        let a = Problem {
+            active: vec![line([&[0], &[0]]), line([&[1], &[1]]), line([&[2], &[2]])],
+            passive: vec![line([&[0], &[1, 2]]), line([&[1], &[2]])],
+        };
+        let b = Problem {
+            active: vec![line([&[2], &[2]]), line([&[0], &[0]]), line([&[1], &[1]])],
+            passive: vec![line([&[1], &[0, 2]]), line([&[2], &[0]])],
+        };
+        let renamed = rename(&a, &are_isomorphic(&a, &b).unwrap());
+        assert_eq!(encode(&renamed.active), encode(&b.active));
+        assert_eq!(encode(&renamed.passive), encode(&b.passive));
+    }
+
+    /// `k`-coloring with colors `first..first + k`
+    fn coloring(k: usize, first: usize) -> Problem<1, 2, 2> {
+        let colors = first..first + k;
+        Problem {
+            active: colors.clone().map(|c| line([&[c], &[c]])).collect(),
+            passive: colors
+                .clone()
+                .flat_map(|a| colors.clone().filter(move |b| a < *b).map(move |b| (a, b)))
+                .map(|(a, b)| line([&[a], &[b]]))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn many_symmetric_labels() {
+        // Without skipping orderings that are related by automorphisms, this takes 10! leaves.
+        let start = Instant::now();
+        for k in 8..=10 {
+            let renaming = are_isomorphic(&coloring(k, 0), &coloring(k, 64 - k)).unwrap();
+            assert!(renaming[..k].iter().all(|x| *x >= 64 - k));
+        }
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn labels_alike_to_the_refinement() {
+        // Every label is in two lines, but those of cycles of different lengths
+        // aren't interchangeable.
+        let cycles = |lengths: &[usize], labels: [usize; 12]| {
+            let mut passive = vec![];
+            let mut start = 0;
+            for len in lengths {
+                for i in 0..*len {
+                    let next = start + (i + 1) % len;
+                    passive.push(line([&[labels[start + i]], &[labels[next]]]));
+                }
+                start += len;
+            }
+            Problem::<1, 2, 2> {
+                active: vec![],
+                passive,
+            }
+        };
+        let identity = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        let a = cycles(&[6, 3, 3], identity);
+        let b = cycles(&[6, 3, 3], [11, 9, 5, 8, 7, 3, 1, 2, 10, 4, 6, 0]);
+        let renamed = rename(&a, &are_isomorphic(&a, &b).unwrap());
+        assert_eq!(encode(&renamed.passive), encode(&b.passive));
+
+        assert_eq!(are_isomorphic(&a, &cycles(&[4, 4, 4], identity)), None);
+        assert_eq!(are_isomorphic(&a, &cycles(&[12], identity)), None);
+    }
+
+    #[test]
+    fn different_problems() {
+        let a = Problem {
+            active: vec![line([&[0], &[1]])],
+            passive: vec![line([&[0], &[1]])],
+        };
+        let b = Problem {
+            active: vec![line([&[0], &[1]])],
+            passive: vec![line([&[0], &[0]])],
+        };
+        assert_eq!(are_isomorphic(&a, &b), None);
+    }
+
+    #[quickcheck]
+    fn renamed_lines_are_isomorphic(lines: Vec<Line<1, 3>>, rotation: usize) -> bool {
+        let renaming = (0..64)
+            .map(|x| (x + rotation % 64) % 64)
+            .collect::<Vec<_>>();
+        let a = Problem::<1, 3, 3> {
+            active: lines.clone(),
+            passive: vec![],
+        };
+        let b = Problem {
+            active: rename_lines(&lines, &renaming),
+            passive: vec![],
+        };
+
+        let renamed = rename(&a, &are_isomorphic(&a, &b).unwrap());
+        encode(&renamed.active) == encode(&b.active)
+    }
+
+    /// Graphs on few labels often have automorphisms that the search skips by.
+    #[quickcheck]
+    fn shuffled_graphs_are_isomorphic(edges: Vec<(u8, u8)>, keys: Vec<u8>) -> bool {
+        let lines = edges
+            .iter()
+            .map(|(a, b)| line([&[*a as usize % 7], &[*b as usize % 7]]))
+            .collect::<Vec<_>>();
+        let mut renaming = (0..7).collect::<Vec<_>>();
+        renaming.sort_by_key(|x| keys.get(*x));
+
+        let a = Problem::<1, 2, 2> {
+            active: vec![],
+            passive: lines.clone(),
+        };
+        let b = Problem {
+            active: vec![],
+            passive: rename_lines(&lines, &renaming),
+        };
+
+        let renamed = rename(&a, &are_isomorphic(&a, &b).unwrap());
+        encode(&renamed.passive) == encode(&b.passive)
+    }
+}
@@ -0,0 +1,129 @@
+use crate::bitarray::{zero, BitArray};
+use crate::line::Line;
+use std::collections::HashMap;
+
+/// Maps label names to the bit indices used in label sets and back.
+#[derive(Clone, Debug)]
+pub struct LabelTable {
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl LabelTable {
+    /// Creates a table of the given names, numbered in sorted order.
+    pub fn new(names: impl IntoIterator<Item = String>) -> Self {
+        let mut names: Vec<String> = names.into_iter().collect();
+        names.sort();
+        names.dedup();
+        Self::from_ordered(names)
+    }
+
+    /// Creates a table of `count` fresh names: `A` to `Z`, `a` to `z`,
+    /// then the same letters followed by 1, 2 and so on.
+    pub fn fresh(count: usize) -> Self {
+        let letters = ('A'..='Z').chain('a'..='z').collect::<Vec<_>>();
+        Self::from_ordered(
+            (0..count)
+                .map(|i| match i / letters.len() {
+                    0 => letters[i].to_string(),
+                    n => format!("{}{}", letters[i % letters.len()], n),
+                })
+                .collect(),
+        )
+    }
+
+    fn from_ordered(names: Vec<String>) -> Self {
+        let indices = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), i))
+            .collect();
+        Self { names, indices }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).cloned()
+    }
+
+    pub fn name(&self, index: usize) -> &str {
+        &self.names[index]
+    }
+
+    /// Returns the set of the named labels or `None` if one of them is not in the table.
+    pub fn to_set<const C: usize>(&self, names: &[String]) -> Option<BitArray<C>> {
+        let mut out = zero();
+        for name in names {
+            out.set(self.index(name)?);
+        }
+        Some(out)
+    }
+
+    /// Writes a set so that it can be parsed again.
+    /// Sets of single-character labels are written as a run like `ABC`,
+    /// others in brackets like `[M0 P1]` so that digits and groups aren't misread.
+    pub fn show_set<const C: usize>(&self, set: &BitArray<C>) -> String {
+        let names = self
+            .names
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| set.get(*i))
+            .map(|(_, x)| x.as_str())
+            .collect::<Vec<_>>();
+
+        let plain =
+            |x: &&str| x.chars().count() == 1 && !x.starts_with(|c: char| c.is_ascii_digit());
+        if names.len() == 1 || names.iter().all(plain) {
+            names.concat()
+        } else {
+            format!("[{}]", names.join(" "))
+        }
+    }
+
+    pub fn show_line<const C: usize, const D: usize>(&self, line: &Line<C, D>) -> String {
+        self.show_sets(&line.0)
+    }
+
+    /// Like [`LabelTable::show_line`] for lines whose arity is only known at runtime.
+    pub fn show_sets<const C: usize>(&self, sets: &[BitArray<C>]) -> String {
+        let mut tmp = sets.iter().map(|s| self.show_set(s)).collect::<Vec<_>>();
+        tmp.sort();
+        tmp.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_line;
+
+    #[test]
+    fn shown_sets_parse_back() {
+        let labels = LabelTable::new(
+            ["A", "B", "0", "M0", "X(AB)", "Δ"]
+                .iter()
+                .map(|x| x.to_string()),
+        );
+        for names in [&["A", "B", "Δ"][..], &["A", "0"], &["M0", "X(AB)"], &["0"]] {
+            let names = names.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+            let set: BitArray<1> = labels.to_set(&names).unwrap();
+            let parsed = parse_line(&labels.show_set(&set), None).unwrap();
+            assert_eq!(labels.to_set(&parsed[0][0]), Some(set));
+        }
+    }
+
+    #[test]
+    fn fresh_names_are_distinct() {
+        let labels = LabelTable::fresh(200);
+        for i in 0..200 {
+            assert_eq!(labels.index(labels.name(i)), Some(i));
+        }
+    }
+}
@@ -1,51 +1,316 @@
-#![feature(const_evaluatable_checked)]
-#![feature(const_generics)]
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
 
+pub mod bipartite;
 pub mod bitarray;
-mod line;
+pub mod checkpoint;
+pub mod diagram;
+pub mod fixed_point;
+pub mod harden;
+pub mod index;
+pub mod isomorphism;
+pub mod labels;
+pub mod limits;
+pub mod line;
 pub mod line_superiority;
+pub mod parse;
+pub mod relax;
+pub mod useless;
+pub mod zero_round;
+
+#[cfg(test)]
+mod brute_force;
+#[cfg(test)]
+mod test_util;
 
 use bitarray::BitArray;
+use index::LineIndex;
 use itertools::Itertools;
+use limits::{Budget, Limits, Status};
 use line::Line;
-use line_superiority::is_inferior_to;
-use std::collections::HashSet;
+use line_superiority::{is_inferior_to_with, Matcher, Matching};
+use rayon::prelude::*;
 use std::collections::VecDeque;
 use std::convert::TryInto;
+use useless::UselessCache;
 
-pub fn active_side<const C: usize, const D: usize>(
-    passive: Vec<Vec<BitArray<C>>>,
-    alphabet: Vec<u8>,
-) where
-    [(); D - 1]: Sized,
+/// A problem given as two constraints over the same alphabet.
+/// Each constraint is a list of lines, each of which allows every
+/// configuration that picks one label from each of its sets.
+/// The active lines have arity `A` and the passive lines arity `P`.
+#[derive(Clone, Debug)]
+pub struct Problem<const C: usize, const A: usize, const P: usize> {
+    pub active: Vec<Line<C, A>>,
+    pub passive: Vec<Line<C, P>>,
+}
+
+/// Performs one round elimination step.
+///
+/// The passive constraint is maximized and becomes the new active constraint.
+/// Every set of labels occurring in it is a label of the new problem.
+/// The old active constraint becomes the new passive constraint: it allows
+/// the configurations of new labels that contain some old active configuration.
+///
+/// As the sides swap, so do the arities.
+///
+/// Returns the new problem and the set of old labels that each new label stands for.
+/// Panics if the new labels don't fit into `C` cells; use [`next_problem`] to pick a size for them.
+pub fn round_eliminate<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+) -> (Problem<C, P, A>, Vec<BitArray<C>>)
+where
+    [(); P - 1]: Sized,
 {
-    let show_set = |set: &BitArray<C>| {
-        String::from_utf8(
-            alphabet
-                .iter()
-                .enumerate()
-                .filter(|(i, _)| set.get(*i))
-                .map(|(_, x)| *x)
-                .collect(),
-        )
-        .unwrap()
-    };
+    let maximal = active_side(problem.passive.clone());
+    let labels = labels_of(&maximal);
+    assert!(
+        bitarray::cells_needed(labels.len()) <= C,
+        "The new alphabet does not fit into the label sets."
+    );
 
-    let show_line = |line: &Line<C, D>| {
-        let mut tmp = line.0.iter().map(show_set).collect::<Vec<_>>();
-        tmp.sort();
-        tmp.join(" ")
-    };
+    (next_problem(&maximal, &problem.active, &labels), labels)
+}
 
-    let mut todo: VecDeque<Line<C, D>> = passive
-        .into_iter()
-        .map(|line| Line(line.try_into().unwrap()))
+/// Replaces both constraints by their maximal lines.
+pub fn maximize<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+) -> Problem<C, A, P>
+where
+    [(); A - 1]: Sized,
+    [(); P - 1]: Sized,
+{
+    Problem {
+        active: active_side(problem.active.clone()),
+        passive: active_side(problem.passive.clone()),
+    }
+}
+
+/// Returns the distinct sets occurring in `lines` in sorted order.
+pub fn labels_of<const C: usize, const D: usize>(lines: &[Line<C, D>]) -> Vec<BitArray<C>> {
+    lines
+        .iter()
+        .flat_map(|line| line.0.iter().cloned())
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+/// Builds the result of round elimination from the maximal lines of the old passive constraint,
+/// the old active constraint and the labels returned by [`labels_of`] for the maximal lines.
+/// `C2` has to be large enough for the new labels.
+pub fn next_problem<const C: usize, const C2: usize, const A: usize, const P: usize>(
+    maximal: &[Line<C, P>],
+    active: &[Line<C, A>],
+    labels: &[BitArray<C>],
+) -> Problem<C2, P, A> {
+    let active_lines = maximal
+        .iter()
+        .map(|line| {
+            let mut sets = [bitarray::zero(); P];
+            for (new, old) in sets.iter_mut().zip(new_labels(&line.0, labels)) {
+                *new = old;
+            }
+            Line(sets)
+        })
         .collect();
-    let mut done: Vec<Line<C, D>> = vec![];
 
-    let mut useless: HashSet<[BitArray<C>; D]> = HashSet::new();
+    Problem {
+        active: active_lines,
+        passive: passive_side(active, labels),
+    }
+}
+
+/// Translates sets of old labels to the new labels returned by [`labels_of`]:
+/// each set becomes the singleton of its position in `labels`, which must contain it.
+pub fn new_labels<'a, const C: usize, const C2: usize>(
+    sets: &'a [BitArray<C>],
+    labels: &'a [BitArray<C>],
+) -> impl Iterator<Item = BitArray<C2>> + 'a {
+    sets.iter().map(move |set| {
+        let mut out = bitarray::zero();
+        // SAFETY: the caller guarantees that `labels` has every set
+        out.set(labels.binary_search(set).unwrap());
+        out
+    })
+}
+
+/// Computes the passive constraint over `labels`, each of which is a set of old labels.
+/// A configuration is allowed if one can pick an old label from each of its sets
+/// so that the result is allowed by `active`. Lines that are inferior to others are left out.
+pub fn passive_side<const C: usize, const C2: usize, const D: usize>(
+    active: &[Line<C, D>],
+    labels: &[BitArray<C>],
+) -> Vec<Line<C2, D>> {
+    passive_side_with(active, labels, &Matching::default())
+}
+
+/// Like [`passive_side`] but compares lines with `matcher`.
+pub fn passive_side_with<const C: usize, const C2: usize, const D: usize>(
+    active: &[Line<C, D>],
+    labels: &[BitArray<C>],
+    matcher: &impl Matcher,
+) -> Vec<Line<C2, D>> {
+    let mut passive: Vec<Line<C2, D>> = vec![];
+    for new in active.iter().filter_map(|line| line.existential(labels)) {
+        if passive
+            .iter()
+            .any(|x| is_inferior_to_with(matcher, &new, x))
+        {
+            continue;
+        }
+        passive.retain(|x| !is_inferior_to_with(matcher, x, &new));
+        passive.push(new);
+    }
+    passive
+}
+
+/// Computes the maximal lines all of whose configurations are allowed by `passive`.
+pub fn active_side<const C: usize, const D: usize>(passive: Vec<Line<C, D>>) -> Vec<Line<C, D>>
+where
+    [(); D - 1]: Sized,
+{
+    active_side_traced(passive, |_| {})
+}
+
+/// Like [`active_side`] but compares lines with `matcher`.
+pub fn active_side_with<const C: usize, const D: usize>(
+    passive: Vec<Line<C, D>>,
+    matcher: &(impl Matcher + Sync),
+) -> Vec<Line<C, D>>
+where
+    [(); D - 1]: Sized,
+{
+    let mut state = SearchState::with_matcher(passive, matcher);
+    resume_active_side(&mut state, &Limits::default(), |_| {}, |_| {});
+    state.done
+}
+
+/// A step taken by [`active_side_traced`].
+pub enum Event<'a, const C: usize, const D: usize> {
+    /// `new` was obtained by combining `via` with some other line.
+    Found {
+        new: &'a Line<C, D>,
+        via: &'a Line<C, D>,
+    },
+    /// `old` is inferior to `new` and was dropped before being processed.
+    RemovedFromTodo {
+        old: &'a Line<C, D>,
+        new: &'a Line<C, D>,
+    },
+    /// `old` is inferior to `new` and was dropped after being processed.
+    RemovedFromDone {
+        old: &'a Line<C, D>,
+        new: &'a Line<C, D>,
+    },
+}
+
+/// Like [`active_side`] but reports every step of the search to `log`.
+pub fn active_side_traced<const C: usize, const D: usize>(
+    passive: Vec<Line<C, D>>,
+    log: impl FnMut(Event<C, D>),
+) -> Vec<Line<C, D>>
+where
+    [(); D - 1]: Sized,
+{
+    let mut state = SearchState::new(passive);
+    resume_active_side(&mut state, &Limits::default(), log, |_| {});
+    state.done
+}
+
+/// Like [`active_side`] but stops once one of the `limits` is exceeded.
+/// Then the lines found so far are returned, which are allowed but not necessarily maximal.
+pub fn active_side_limited<const C: usize, const D: usize>(
+    passive: Vec<Line<C, D>>,
+    limits: &Limits,
+) -> (Vec<Line<C, D>>, Status)
+where
+    [(); D - 1]: Sized,
+{
+    let mut state = SearchState::new(passive);
+    let status = resume_active_side(&mut state, limits, |_| {}, |_| {});
+    (state.into_lines(), status)
+}
+
+/// The state of the search done by [`active_side`] before a line is taken from `todo`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchState<const C: usize, const D: usize, M = Matching> {
+    pub todo: VecDeque<Line<C, D>>,
+    pub done: Vec<Line<C, D>>,
+    pub useless: UselessCache<C, D>,
+    /// How lines are compared.
+    pub matcher: M,
+}
+
+impl<const C: usize, const D: usize> SearchState<C, D> {
+    /// Starts a search that compares lines with the default [`Matching`].
+    pub fn new(passive: Vec<Line<C, D>>) -> Self {
+        Self::with_matcher(passive, Matching::default())
+    }
+}
+
+impl<const C: usize, const D: usize, M: Matcher> SearchState<C, D, M> {
+    /// Lines that are found are compared with all others, but those in `passive` aren't,
+    /// so duplicates and lines inferior to others are left out here.
+    pub fn with_matcher(passive: Vec<Line<C, D>>, matcher: M) -> Self {
+        let mut todo: VecDeque<Line<C, D>> = VecDeque::new();
+        for line in passive {
+            if todo.iter().any(|x| is_inferior_to_with(&matcher, &line, x)) {
+                continue;
+            }
+            todo.retain(|x| !is_inferior_to_with(&matcher, x, &line));
+            todo.push_back(line);
+        }
+        Self {
+            todo,
+            done: vec![],
+            useless: UselessCache::default(),
+            matcher,
+        }
+    }
+
+    /// Returns the lines in `done` followed by those in `todo`.
+    /// Once the search is complete, these are the maximal lines.
+    pub fn into_lines(self) -> Vec<Line<C, D>> {
+        let mut lines = self.done;
+        lines.extend(self.todo);
+        lines
+    }
+}
+
+/// How many lines of `done` are combined with a new line at once.
+/// Fixed so that the result doesn't depend on the number of threads.
+const CHUNK_SIZE: usize = 64;
+
+/// Continues the search of [`active_side_traced`] from `state`.
+/// `checkpoint` is called with the state every time a line from `todo` has been processed,
+/// so resuming from any of these states gives the same result as not stopping.
+///
+/// The combinations of each line with the lines in `done` are computed in parallel in chunks,
+/// along with checking them against the lines known before the chunk. Then they are added
+/// one after another, so the result doesn't depend on the number of threads.
+///
+/// The limits are checked after every line that leaves work in `todo`, right after
+/// `checkpoint` is called. If one is exceeded, the search stops there,
+/// so it can be resumed from `state`.
+pub fn resume_active_side<const C: usize, const D: usize, M: Matcher + Clone + Sync>(
+    state: &mut SearchState<C, D, M>,
+    limits: &Limits,
+    mut log: impl FnMut(Event<C, D>),
+    mut checkpoint: impl FnMut(&SearchState<C, D, M>),
+) -> Status
+where
+    [(); D - 1]: Sized,
+{
+    let mut budget = Budget::new(limits);
+    let mut index = LineIndex::new(state.todo.iter().chain(&state.done), state.matcher.clone());
+    while let Some(line) = state.todo.pop_front() {
+        let SearchState {
+            todo,
+            done,
+            useless,
+            matcher,
+        } = &mut *state;
 
-    while let Some(line) = todo.pop_front() {
         done.push(line.clone());
 
         let perms: Vec<Line<C, D>> = line
@@ -58,78 +323,308 @@ pub fn active_side<const C: usize, const D: usize>(
 
         let mut i = 0;
         while i < done.len() {
-            let mut next_i = i + 1;
+            let mut next_i = (i + CHUNK_SIZE).min(done.len());
+            let results: Vec<_> = done[i..next_i]
+                .par_iter()
+                .map(|other| combinations(other, &perms, useless, matcher, &index))
+                .collect();
 
-            let mut candidates = vec![];
-            'outer: for mut p in perms.iter().flat_map(|p| done[i].combine_with(p)) {
-                p.0.sort();
-                if useless.contains(&p.0) {
-                    continue;
-                }
+            for found in results {
+                useless.extend(found.useless);
+                useless.record_hits(&found.hits);
+                budget.add_combinations(found.count);
 
-                for c in &candidates {
-                    if *c >= p {
-                        useless.insert(p.0);
-                        continue 'outer;
-                    }
-                }
-                candidates.retain(|c| !(p >= *c));
-                candidates.push(p);
-            }
-
-            'new_lines: for new in candidates {
-                for x in todo.iter().chain(&done) {
-                    if *x >= new {
+                for new in found.candidates {
+                    // Only lines added since the parallel check can dominate it now
+                    if index.is_dominated(&new) {
                         useless.insert(new.0);
-                        continue 'new_lines;
+                        continue;
                     }
-                }
 
-                println!("found: {} via {}", show_line(&new), show_line(&line));
+                    log(Event::Found {
+                        new: &new,
+                        via: &line,
+                    });
 
-                // Remove lines obsoleted by newly found ones
-                {
+                    // Remove lines obsoleted by newly found ones. The index has every line
+                    // of `todo` and `done` once, so the search stops when all are found.
+                    let mut obsolete = index.remove_inferior_to(&new);
                     let mut i = 0;
-                    while i < todo.len() {
-                        if is_inferior_to(&todo[i], &new) {
-                            println!(
-                                "removed from todo: {} < {}",
-                                show_line(&todo[i]),
-                                show_line(&new)
-                            );
+                    while !obsolete.is_empty() && i < todo.len() {
+                        if take(&mut obsolete, &todo[i]) {
+                            log(Event::RemovedFromTodo {
+                                old: &todo[i],
+                                new: &new,
+                            });
                             todo.swap_remove_back(i);
                         } else {
                             i += 1;
                         }
                     }
-                }
 
-                let mut written = 0;
-                for j in 0..done.len() {
-                    if is_inferior_to(&done[j], &new) {
-                        println!(
-                            "removed from done: {} < {}",
-                            show_line(&done[j]),
-                            show_line(&new)
-                        );
-                        if j < next_i {
-                            next_i -= 1;
+                    let mut j = 0;
+                    done.retain(|old| {
+                        let inferior = !obsolete.is_empty() && take(&mut obsolete, old);
+                        if inferior {
+                            log(Event::RemovedFromDone { old, new: &new });
+                            if j < next_i {
+                                next_i -= 1;
+                            }
+                        } else {
+                            j += 1;
                         }
-                    } else {
-                        // TODO Unnecessary clone here.
-                        done[written] = done[j].clone();
-                        written += 1;
-                    }
-                }
-                done.truncate(written);
+                        !inferior
+                    });
 
-                todo.push_back(new);
+                    index.insert(new.clone());
+                    todo.push_back(new);
+                }
             }
             i = next_i;
         }
+
+        checkpoint(state);
+        if state.todo.is_empty() {
+            return Status::Complete;
+        }
+        if let Some(status) = budget.exceeded(state.done.len()) {
+            return status;
+        }
+    }
+
+    Status::Complete
+}
+
+/// Removes a line equal to `line` from `lines`. Returns whether there was one.
+fn take<const C: usize, const D: usize>(lines: &mut Vec<Line<C, D>>, line: &Line<C, D>) -> bool {
+    match lines.iter().position(|x| x == line) {
+        Some(i) => {
+            lines.swap_remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// The results of combining a line of `done` with a new line.
+struct Combinations<const C: usize, const D: usize> {
+    /// The results that aren't known to be useless or inferior to each other.
+    candidates: Vec<Line<C, D>>,
+    /// The results that turned out to be inferior to other results.
+    useless: Vec<[BitArray<C>; D]>,
+    /// The results that were skipped because they were known to be useless.
+    hits: Vec<[BitArray<C>; D]>,
+    count: usize,
+}
+
+/// Combines `other` with every permutation of a line.
+/// Results dominated by a line in `index` are moved to `useless`, which is the bulk of the
+/// domination checks and therefore done here, in parallel for a chunk of `done`.
+fn combinations<const C: usize, const D: usize, M: Matcher>(
+    other: &Line<C, D>,
+    perms: &[Line<C, D>],
+    useless: &UselessCache<C, D>,
+    matcher: &M,
+    index: &LineIndex<C, D, M>,
+) -> Combinations<C, D>
+where
+    [(); D - 1]: Sized,
+{
+    let mut out = Combinations {
+        candidates: vec![],
+        useless: vec![],
+        hits: vec![],
+        count: 0,
+    };
+    'outer: for mut p in perms.iter().flat_map(|p| other.combine_with(p)) {
+        out.count += 1;
+        p.0.sort();
+        if useless.contains(&p.0) {
+            out.hits.push(p.0);
+            continue;
+        }
+
+        for c in &out.candidates {
+            if is_inferior_to_with(matcher, &p, c) {
+                out.useless.push(p.0);
+                continue 'outer;
+            }
+        }
+        out.candidates
+            .retain(|c| !is_inferior_to_with(matcher, c, &p));
+        out.candidates.push(p);
+    }
+
+    let (dominated, candidates): (Vec<_>, Vec<_>) = out
+        .candidates
+        .into_iter()
+        .partition(|c| index.is_dominated(c));
+    out.useless.extend(dominated.into_iter().map(|c| c.0));
+    out.candidates = candidates;
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn three_coloring_on_cycles() {
+        let problem = Problem {
+            active: vec![line([&[0], &[0]]), line([&[1], &[1]]), line([&[2], &[2]])],
+            passive: vec![line([&[0], &[1, 2]]), line([&[1], &[2]])],
+        };
+        let (problem, labels) = round_eliminate(&problem);
+
+        assert_eq!(
+            labels,
+            line([&[0], &[1], &[0, 1], &[2], &[0, 2], &[1, 2]]).0
+        );
+        assert_eq!(problem.active.len(), 3);
+        for expected in [line([&[0], &[5]]), line([&[1], &[4]]), line([&[2], &[3]])] {
+            assert!(problem.active.contains(&expected));
+        }
+        assert_eq!(problem.passive.len(), 3);
+        for expected in [
+            line([&[0, 2, 4], &[0, 2, 4]]),
+            line([&[1, 2, 5], &[1, 2, 5]]),
+            line([&[3, 4, 5], &[3, 4, 5]]),
+        ] {
+            assert!(problem.passive.contains(&expected));
+        }
+    }
+
+    #[test]
+    fn redundant_passive_lines_are_dropped() {
+        // The second line is inferior to the first one and the third one is a permutation of it
+        let passive = vec![
+            line([&[0], &[1, 2]]),
+            line([&[2], &[0]]),
+            line([&[1, 2], &[0]]),
+        ];
+        let maximal = active_side(passive);
+        assert_eq!(maximal.len(), 1);
+        assert_eq!(maximal[0], line([&[0], &[1, 2]]));
+    }
+
+    #[test]
+    fn result_does_not_depend_on_matcher() {
+        let passive = vec![
+            line([&[0], &[1, 2], &[3, 4], &[5]]),
+            line([&[1], &[0, 2, 4], &[3], &[5]]),
+            line([&[2], &[0, 1], &[4, 5], &[3]]),
+        ];
+        let expected = active_side(passive.clone());
+        for matcher in Matching::ALL {
+            assert_eq!(active_side_with(passive.clone(), &matcher), expected);
+        }
     }
 
-    let mut strings = done.iter().map(show_line).collect::<Vec<_>>();
-    strings.sort();
-    println!("{}", strings.join("\n"));
+    #[test]
+    fn own_matcher_is_used_by_the_search() {
+        struct Counting(AtomicUsize);
+        impl Matcher for Counting {
+            fn has_perfect_matching<const D: usize>(&self, neighbors_a: &[&[usize]; D]) -> bool {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Matching::default().has_perfect_matching(neighbors_a)
+            }
+        }
+
+        let passive = vec![line([&[0], &[1, 2]]), line([&[1], &[2]])];
+        let counting = Counting(AtomicUsize::new(0));
+        assert_eq!(
+            active_side_with(passive.clone(), &counting),
+            active_side(passive)
+        );
+        assert!(counting.0.load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn passive_side_does_not_depend_on_matcher() {
+        let active = vec![
+            line([&[0], &[0]]),
+            line([&[1], &[1]]),
+            line([&[2], &[2]]),
+            line([&[0, 1], &[2]]),
+        ];
+        let labels = labels_of(&[
+            line([&[0], &[1, 2]]),
+            line([&[1], &[0, 2]]),
+            line([&[0, 1], &[2]]),
+        ]);
+        let expected: Vec<Line<1, 2>> = passive_side(&active, &labels);
+        for matcher in Matching::ALL {
+            assert_eq!(passive_side_with(&active, &labels, &matcher), expected);
+        }
+    }
+
+    #[test]
+    fn result_does_not_depend_on_threads() {
+        let passive = vec![
+            line([&[0], &[1, 2], &[3, 4], &[5]]),
+            line([&[1], &[0, 2, 4], &[3], &[5]]),
+            line([&[2], &[0, 1], &[4, 5], &[3]]),
+            line([&[3], &[4], &[0, 1, 5], &[2]]),
+        ];
+        let with_threads = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| active_side(passive.clone()))
+        };
+        let expected = with_threads(1);
+        for threads in [2, 4] {
+            let result = with_threads(threads);
+            assert_eq!(result.len(), expected.len());
+            for (a, b) in result.iter().zip(&expected) {
+                assert_eq!(a.0, b.0);
+            }
+        }
+    }
+
+    #[test]
+    fn limits_give_partial_results() {
+        let passive = vec![line([&[0], &[1, 2]]), line([&[1], &[2]])];
+        let (all, status) = active_side_limited(passive.clone(), &Limits::default());
+        assert_eq!(status, Status::Complete);
+
+        let limits = Limits {
+            done: Some(1),
+            ..Limits::default()
+        };
+        let (partial, status) = active_side_limited(passive, &limits);
+        assert_eq!(status, Status::DoneLimit);
+        assert!(!partial.is_empty());
+        assert!(partial.iter().all(|x| all.iter().any(|y| y >= x)));
+    }
+
+    #[test]
+    fn finished_search_is_complete_despite_limits() {
+        let limits = Limits {
+            done: Some(0),
+            combinations: Some(0),
+            time: Some(Duration::from_secs(0)),
+        };
+        let (lines, status) = active_side_limited(vec![line([&[0], &[1]])], &limits);
+        assert_eq!(status, Status::Complete);
+        assert_eq!(lines, vec![line([&[0], &[1]])]);
+    }
+
+    #[test]
+    fn sinkless_orientation_in_edge_formalism() {
+        // 0 is incoming and 1 outgoing
+        let problem = Problem {
+            active: vec![line([&[1], &[0, 1], &[0, 1]])],
+            passive: vec![line([&[0], &[1]])],
+        };
+        let (problem, labels) = round_eliminate(&problem);
+
+        assert_eq!(labels, line([&[0], &[1]]).0);
+        assert_eq!(problem.active, vec![line([&[0], &[1]])]);
+        assert_eq!(problem.passive, vec![line([&[1], &[0, 1], &[0, 1]])]);
+    }
 }
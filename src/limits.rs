@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+/// Bounds on the resources a search may use. `None` means unbounded.
+///
+/// The limits are soft: they are only checked after a line has been combined
+/// with all lines in `done`, and a search stops once a limit is exceeded, not reached.
+/// So with `done: Some(n)`, more than `n` lines may end up in `done`, and the time limit
+/// may be overshot by the time it takes to process one line.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Limits {
+    /// Wall time since the start of the search.
+    pub time: Option<Duration>,
+    /// Number of lines in `done`.
+    pub done: Option<usize>,
+    /// Number of combinations of two lines that were computed.
+    pub combinations: Option<usize>,
+}
+
+/// Whether a search finished or which limit stopped it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Complete,
+    TimeLimit,
+    DoneLimit,
+    CombinationLimit,
+}
+
+impl Status {
+    pub fn is_complete(self) -> bool {
+        self == Status::Complete
+    }
+}
+
+/// Keeps track of the resources used so far.
+pub(crate) struct Budget {
+    limits: Limits,
+    start: Instant,
+    combinations: usize,
+}
+
+impl Budget {
+    pub(crate) fn new(limits: &Limits) -> Self {
+        Self {
+            limits: *limits,
+            start: Instant::now(),
+            combinations: 0,
+        }
+    }
+
+    pub(crate) fn add_combinations(&mut self, count: usize) {
+        self.combinations += count;
+    }
+
+    /// Returns the first limit that is exceeded, if any.
+    pub(crate) fn exceeded(&self, done: usize) -> Option<Status> {
+        if over(self.limits.time, self.start.elapsed()) {
+            Some(Status::TimeLimit)
+        } else if over(self.limits.done, done) {
+            Some(Status::DoneLimit)
+        } else if over(self.limits.combinations, self.combinations) {
+            Some(Status::CombinationLimit)
+        } else {
+            None
+        }
+    }
+}
+
+fn over<T: PartialOrd>(limit: Option<T>, value: T) -> bool {
+    match limit {
+        Some(limit) => value > limit,
+        None => false,
+    }
+}
@@ -1,4 +1,5 @@
 use crate::bitarray::{zero, BitArray};
+use crate::line_superiority::is_inferior_to;
 use std::cmp::Ordering;
 
 #[derive(Clone, Debug)]
@@ -76,9 +77,23 @@ where
 
 impl<const C: usize, const D: usize> Line<C, D> {
     /// The number of symbols allowed in total
-    fn size(&self) -> usize {
+    pub fn size(&self) -> usize {
         self.0.iter().map(|x| x.size()).sum()
     }
+
+    /// Translates this line to the `labels`, each of which is a set of the current labels.
+    /// The result allows exactly the configurations of `labels` from which this line
+    /// allows at least one choice. Returns `None` if no such configuration exists.
+    pub fn existential<const C2: usize>(&self, labels: &[BitArray<C>]) -> Option<Line<C2, D>> {
+        let mut out = [zero(); D];
+        for (new, old) in out.iter_mut().zip(&self.0) {
+            *new = old.existential(labels);
+            if *new == zero() {
+                return None;
+            }
+        }
+        Some(Line(out))
+    }
 }
 
 impl<const C: usize, const D: usize> Eq for Line<C, D> {}
@@ -116,21 +131,7 @@ impl<const C: usize, const D: usize> PartialOrd<Self> for Line<C, D> {
     }
 
     fn ge(&self, other: &Self) -> bool {
-        let mut stack = vec![(0, [false; D])];
-
-        while let Some((i, used)) = stack.pop() {
-            for (j, o) in other.0.iter().enumerate() {
-                if !used[j] && *o & self.0[i] == *o {
-                    if i == D - 1 {
-                        return true;
-                    }
-                    let mut used2 = used.clone();
-                    used2[j] = true;
-                    stack.push((i + 1, used2));
-                }
-            }
-        }
-        false
+        is_inferior_to(other, self)
     }
 }
 
@@ -1,30 +1,111 @@
+use crate::bipartite;
 use crate::line::Line;
 use std::collections::VecDeque;
-use std::convert::TryInto;
 
+/// Decides whether a bipartite graph with `D` vertices on each side has a perfect matching.
+/// `neighbors_a[i]` lists the vertices on the second side that vertex `i` is adjacent to.
+pub trait Matcher {
+    fn has_perfect_matching<const D: usize>(&self, neighbors_a: &[&[usize]; D]) -> bool;
+}
+
+impl<M: Matcher + ?Sized> Matcher for &M {
+    fn has_perfect_matching<const D: usize>(&self, neighbors_a: &[&[usize]; D]) -> bool {
+        (**self).has_perfect_matching(neighbors_a)
+    }
+}
+
+/// The matching algorithms in this module.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Matching {
+    #[default]
+    Simple,
+    HopcroftKarp,
+    PushRelabel,
+    /// Only for `D` up to 16. Uses Hopcroft-Karp for larger `D`.
+    Bitmask,
+}
+
+impl Matcher for Matching {
+    fn has_perfect_matching<const D: usize>(&self, neighbors_a: &[&[usize]; D]) -> bool {
+        match self {
+            Matching::Simple => maximum_matching_simple(neighbors_a),
+            Matching::HopcroftKarp => maximum_matching_hopcroft_karp(neighbors_a),
+            Matching::PushRelabel => maximum_matching_push_relabel(neighbors_a),
+            Matching::Bitmask if D > 16 => maximum_matching_hopcroft_karp(neighbors_a),
+            Matching::Bitmask => maximum_matching_bitmask(neighbors_a),
+        }
+    }
+}
+
+impl Matching {
+    pub const ALL: [Matching; 4] = [
+        Matching::Simple,
+        Matching::HopcroftKarp,
+        Matching::PushRelabel,
+        Matching::Bitmask,
+    ];
+}
+
+/// Returns whether every set of `side_a` is a subset of a different set of `side_b`,
+/// using the default [`Matching`]. This is how lines are compared with `>=`.
 pub fn is_inferior_to<const C: usize, const D: usize>(
     side_a: &Line<C, D>,
     side_b: &Line<C, D>,
 ) -> bool {
-    let mut storage = vec![];
-    let neighbors_a: [&[usize]; D] = (0..D)
-        .map(|i| {
-            let start = storage.len();
-            for j in 0..D {
-                if side_a.0[i] & side_b.0[j] == side_a.0[i] {
-                    storage.push(j);
-                }
-            }
-            start..storage.len()
-        })
-        .collect::<Vec<_>>()
-        .into_iter()
-        .map(|x| &storage[x])
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
+    is_inferior_to_with(&Matching::default(), side_a, side_b)
+}
 
-    maximum_matching_simple(&neighbors_a)
+/// Like [`is_inferior_to`] with the given matcher.
+pub fn is_inferior_to_with<const C: usize, const D: usize>(
+    matcher: &impl Matcher,
+    side_a: &Line<C, D>,
+    side_b: &Line<C, D>,
+) -> bool {
+    let (storage, lengths) = subsets(side_a, side_b);
+    // No matching can cover a set that fits nowhere
+    if lengths.contains(&0) {
+        return false;
+    }
+
+    let mut neighbors_a: [&[usize]; D] = [&[]; D];
+    for (n, (s, l)) in neighbors_a.iter_mut().zip(storage.iter().zip(&lengths)) {
+        *n = &s[..*l];
+    }
+    matcher.has_perfect_matching(&neighbors_a)
+}
+
+/// Explains [`is_inferior_to`]: returns for each position of `side_a` a different position
+/// of `side_b` whose set contains its set, or positions of `side_a` whose sets
+/// all fit only into fewer positions of `side_b`.
+pub fn explain_inferiority<const C: usize, const D: usize>(
+    side_a: &Line<C, D>,
+    side_b: &Line<C, D>,
+) -> Result<[usize; D], Vec<usize>> {
+    let (storage, lengths) = subsets(side_a, side_b);
+    let mut neighbors_a: [&[usize]; D] = [&[]; D];
+    for (n, (s, l)) in neighbors_a.iter_mut().zip(storage.iter().zip(&lengths)) {
+        *n = &s[..*l];
+    }
+    find_matching_hopcroft_karp(&neighbors_a)
+}
+
+/// Lists for each set of `side_a` the positions of the sets of `side_b` that contain it.
+/// The list for position `i` is `storage[i][..lengths[i]]`.
+fn subsets<const C: usize, const D: usize>(
+    side_a: &Line<C, D>,
+    side_b: &Line<C, D>,
+) -> ([[usize; D]; D], [usize; D]) {
+    let mut storage = [[0; D]; D];
+    let mut lengths = [0; D];
+    for i in 0..D {
+        for j in 0..D {
+            if side_a.0[i] & side_b.0[j] == side_a.0[i] {
+                storage[i][lengths[i]] = j;
+                lengths[i] += 1;
+            }
+        }
+    }
+    (storage, lengths)
 }
 
 pub fn maximum_matching_simple<const D: usize>(neighbors_a: &[&[usize]; D]) -> bool {
@@ -45,106 +126,78 @@ pub fn maximum_matching_simple<const D: usize>(neighbors_a: &[&[usize]; D]) -> b
     false
 }
 
-pub fn maximum_matching_hopcroft_karp<const D: usize>(neighbors_a: &[&[usize]; D]) -> bool {
-    let mut pair_for_b = [None; D];
-    let mut pair_for_a = [0; D];
-
-    let mut starts: Vec<usize> = (0..D).collect();
-
-    while !starts.is_empty() {
-        let mut layer_a = [None; D];
-
-        let mut todo = starts.clone();
-        let mut next = vec![];
-        let mut last_layer = false;
-
-        for layer in 0.. {
-            for a in &todo {
-                if layer_a[*a] != None {
-                    continue;
-                }
-                layer_a[*a] = Some(layer);
-
-                for b in neighbors_a[*a] {
-                    if let Some(ai2) = pair_for_b[*b] {
-                        next.push(ai2);
-                    } else {
-                        last_layer = true;
-                    }
-                }
-            }
-
-            if last_layer {
-                break;
-            }
+/// Matches the vertices of side A in order and remembers which sets of vertices of side B
+/// have been reached that way, so every set is only extended once. Panics if `D` is above 16.
+pub fn maximum_matching_bitmask<const D: usize>(neighbors_a: &[&[usize]; D]) -> bool {
+    assert!(
+        D <= 16,
+        "The bitmask matcher only supports up to 16 vertices."
+    );
+    // There is a bit for every set of vertices of side B, in as few words as fit on the stack
+    match D {
+        0..=6 => bitmask::<D, 1>(neighbors_a),
+        7..=12 => bitmask::<D, 64>(neighbors_a),
+        _ => bitmask::<D, 1024>(neighbors_a),
+    }
+}
 
-            if next.is_empty() {
-                return false;
-            }
+/// [`maximum_matching_bitmask`] with `W` words for the sets of vertices of side B.
+fn bitmask<const D: usize, const W: usize>(neighbors_a: &[&[usize]; D]) -> bool {
+    let mut masks = [0u16; D];
+    for (mask, neighbors) in masks.iter_mut().zip(neighbors_a) {
+        for b in neighbors.iter() {
+            *mask |= 1 << b;
+        }
+    }
 
-            std::mem::swap(&mut todo, &mut next);
-            next.clear();
-        }
-
-        let mut visited = [false; D];
-
-        let mut i = 0;
-        'outer: while i < starts.len() {
-            let mut stack = vec![Try(starts[i])];
-            let mut current_path = vec![];
-
-            while let Some(f) = stack.pop() {
-                match f {
-                    Try(a) => {
-                        if visited[a] {
-                            continue;
-                        }
-                        visited[a] = true;
-                        current_path.push(a);
-                        stack.push(Backtrack);
-
-                        for b in neighbors_a[a] {
-                            if let Some(a2) = pair_for_b[*b] {
-                                if layer_a[*b] == Some(layer_a[a].unwrap() + 1) {
-                                    stack.push(Try(a2));
-                                }
-                            } else {
-                                let mut b = *b;
-                                while let Some(a) = current_path.pop() {
-                                    pair_for_b[b] = Some(a);
-                                    let old_b = b;
-                                    b = pair_for_a[a];
-                                    pair_for_a[a] = old_b;
-                                }
-                                starts.swap_remove(i);
-                                continue 'outer;
-                            }
-                        }
-                    }
-                    Backtrack => {
-                        current_path.pop();
-                    }
-                }
+    let full = (1usize << D) - 1;
+    // Bit `m` of `seen` means that the first `m.count_ones()` vertices can be matched to the set `m`
+    let mut seen = [0u64; W];
+    // The sets of each size on the stack are extensions of the same set,
+    // so there are at most `D` of them per size.
+    let mut stack = [0usize; 16 * 16 + 1];
+    let mut len = 1;
+    while len > 0 {
+        len -= 1;
+        let used = stack[len];
+        if used == full {
+            return true;
+        }
+        let mut free = masks[used.count_ones() as usize] as usize & !used;
+        while free != 0 {
+            let next = used | (free & free.wrapping_neg());
+            free &= free - 1;
+            if seen[next / 64] & 1 << (next % 64) == 0 {
+                seen[next / 64] |= 1 << (next % 64);
+                stack[len] = next;
+                len += 1;
             }
-            i += 1;
         }
     }
+    false
+}
+
+pub fn maximum_matching_hopcroft_karp<const D: usize>(neighbors_a: &[&[usize]; D]) -> bool {
+    hopcroft_karp(neighbors_a).iter().all(|x| x.is_some())
+}
 
-    true
+/// Returns a maximum matching as the partner of each vertex of side A.
+fn hopcroft_karp<const D: usize>(neighbors_a: &[&[usize]; D]) -> [Option<usize>; D] {
+    let mut pair_for_a = [None; D];
+    let mut pair_for_b = [None; D];
+    let mut layer_a = [None; D];
+    bipartite::hopcroft_karp(neighbors_a, &mut pair_for_a, &mut pair_for_b, &mut layer_a);
+    pair_for_a
 }
 
-enum StackFrame {
-    Backtrack,
-    Try(usize),
+pub fn maximum_matching_push_relabel<const D: usize>(neighbors_a: &[&[usize]; D]) -> bool {
+    push_relabel(neighbors_a).iter().all(|x| x.is_some())
 }
-use StackFrame::*;
 
-pub fn maximum_matching_push_relabel<const D: usize>(neighbors_a: &[&[usize]; D]) -> bool
-where
-    [(); 2 * D]: Sized,
-{
-    let mut height = [0; 2 * D];
-    let mut excess = [0; 2 * D];
+/// Returns the matching given by a maximum flow as the partner of each vertex of side A.
+fn push_relabel<const D: usize>(neighbors_a: &[&[usize]; D]) -> [Option<usize>; D] {
+    let mut height = vec![0; 2 * D];
+    let mut excess = vec![0; 2 * D];
     let mut todo = VecDeque::new();
 
     for i in 0..D {
@@ -194,5 +247,269 @@ where
         }
     }
 
-    excess[D..].iter().all(|&x| x == 0)
+    // A vertex of side B only has an edge back to side A if it receives flow from there
+    let mut pair_for_a = [None; D];
+    for b in 0..D {
+        for &a in &neighbors[D + b] {
+            pair_for_a[a] = Some(b);
+        }
+    }
+    pair_for_a
+}
+
+/// Like [`maximum_matching_simple`] but returns the matching, where `matching[a]` is the partner
+/// of `a`, or a Hall violator: vertices of side A that have fewer neighbors than there are of them.
+pub fn find_matching_simple<const D: usize>(
+    neighbors_a: &[&[usize]; D],
+) -> Result<[usize; D], Vec<usize>> {
+    let mut stack = vec![(0, [false; D], [0; D])];
+
+    while let Some((i, used, matching)) = stack.pop() {
+        for n in neighbors_a[i] {
+            if !used[*n] {
+                let mut matching2 = matching;
+                matching2[i] = *n;
+                if i == D - 1 {
+                    return Ok(matching2);
+                }
+                let mut used2 = used;
+                used2[*n] = true;
+                stack.push((i + 1, used2, matching2));
+            }
+        }
+    }
+    complete_matching(neighbors_a, [None; D])
+}
+
+/// Like [`find_matching_simple`] using [`maximum_matching_hopcroft_karp`].
+pub fn find_matching_hopcroft_karp<const D: usize>(
+    neighbors_a: &[&[usize]; D],
+) -> Result<[usize; D], Vec<usize>> {
+    complete_matching(neighbors_a, hopcroft_karp(neighbors_a))
+}
+
+/// Like [`find_matching_simple`] using [`maximum_matching_push_relabel`].
+pub fn find_matching_push_relabel<const D: usize>(
+    neighbors_a: &[&[usize]; D],
+) -> Result<[usize; D], Vec<usize>> {
+    complete_matching(neighbors_a, push_relabel(neighbors_a))
+}
+
+/// Extends a matching along augmenting paths until it is perfect.
+/// If there is no augmenting path from some unmatched vertex, the vertices of side A
+/// reachable from it by alternating paths are returned as a Hall violator:
+/// all their neighbors are matched to the others among them.
+fn complete_matching<const D: usize>(
+    neighbors_a: &[&[usize]; D],
+    mut pair_for_a: [Option<usize>; D],
+) -> Result<[usize; D], Vec<usize>> {
+    let mut pair_for_b = [None; D];
+    for (a, b) in pair_for_a.iter().enumerate() {
+        if let Some(b) = b {
+            pair_for_b[*b] = Some(a);
+        }
+    }
+
+    while let Some(start) = pair_for_a.iter().position(|x| x.is_none()) {
+        let mut reached_a = [false; D];
+        let mut parent_b: [Option<usize>; D] = [None; D];
+        reached_a[start] = true;
+        let mut todo = VecDeque::new();
+        todo.push_back(start);
+
+        let mut free_b = None;
+        'search: while let Some(a) = todo.pop_front() {
+            for &b in neighbors_a[a] {
+                if parent_b[b].is_some() {
+                    continue;
+                }
+                parent_b[b] = Some(a);
+                match pair_for_b[b] {
+                    None => {
+                        free_b = Some(b);
+                        break 'search;
+                    }
+                    Some(a2) if !reached_a[a2] => {
+                        reached_a[a2] = true;
+                        todo.push_back(a2);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let mut b = match free_b {
+            Some(b) => b,
+            None => return Err((0..D).filter(|a| reached_a[*a]).collect()),
+        };
+        // SAFETY: every vertex on the path was reached from a vertex of side A
+        while let Some(old_b) = pair_for_a[parent_b[b].unwrap()].replace(b) {
+            pair_for_b[b] = parent_b[b];
+            b = old_b;
+        }
+        pair_for_b[b] = parent_b[b];
+    }
+
+    let mut matching = [0; D];
+    for (m, pair) in matching.iter_mut().zip(&pair_for_a) {
+        // SAFETY: the loop above only ends once every vertex is matched
+        *m = pair.unwrap();
+    }
+    Ok(matching)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::*;
+    use std::convert::TryInto;
+
+    /// Neighbor lists of a graph with `D` vertices on each side. Shrinking removes edges,
+    /// so that a failing case is reported with as few edges as possible.
+    #[derive(Clone, Debug)]
+    struct Graph<const D: usize>([Vec<usize>; D]);
+
+    impl<const D: usize> Arbitrary for Graph<D> {
+        fn arbitrary(g: &mut Gen) -> Self {
+            // Every vertex gets its own density, so that graphs with and without
+            // a perfect matching are both common.
+            let lists = (0..D)
+                .map(|_| {
+                    let density = usize::arbitrary(g) % (D + 1);
+                    (0..D)
+                        .filter(|_| usize::arbitrary(g) % D < density)
+                        .collect()
+                })
+                .collect::<Vec<_>>();
+            Self(lists.try_into().unwrap())
+        }
+
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            let graph = self.clone();
+            Box::new((0..D).flat_map(move |a| {
+                let graph = graph.clone();
+                (0..graph.0[a].len()).map(move |i| {
+                    let mut smaller = graph.clone();
+                    smaller.0[a].remove(i);
+                    smaller
+                })
+            }))
+        }
+    }
+
+    /// Checks that all matchers agree with the simple one and that the matchings
+    /// and Hall violators they find are correct.
+    fn agree<const D: usize>(graph: Graph<D>) -> bool {
+        let mut neighbors: [&[usize]; D] = [&[]; D];
+        for (n, list) in neighbors.iter_mut().zip(&graph.0) {
+            *n = list;
+        }
+        let expected = maximum_matching_simple(&neighbors);
+
+        let certified = |found: Result<[usize; D], Vec<usize>>| match found {
+            Ok(matching) => {
+                let mut partners = matching.to_vec();
+                partners.sort_unstable();
+                partners.dedup();
+                expected
+                    && partners.len() == D
+                    && (0..D).all(|a| neighbors[a].contains(&matching[a]))
+            }
+            Err(violator) => {
+                let mut reachable = violator
+                    .iter()
+                    .flat_map(|a| neighbors[*a].iter())
+                    .collect::<Vec<_>>();
+                reachable.sort_unstable();
+                reachable.dedup();
+                !expected && reachable.len() < violator.len()
+            }
+        };
+
+        Matching::ALL
+            .iter()
+            .all(|m| m.has_perfect_matching(&neighbors) == expected)
+            && [
+                find_matching_simple,
+                find_matching_hopcroft_karp,
+                find_matching_push_relabel,
+            ]
+            .iter()
+            .all(|find| certified(find(&neighbors)))
+    }
+
+    macro_rules! differential_tests {
+        ($($name:ident: $d:literal),*) => {$(
+            #[quickcheck]
+            fn $name(graph: Graph<$d>) -> bool {
+                agree(graph)
+            }
+        )*};
+    }
+
+    differential_tests!(
+        matchers_agree_1: 1,
+        matchers_agree_2: 2,
+        matchers_agree_3: 3,
+        matchers_agree_4: 4,
+        matchers_agree_5: 5,
+        matchers_agree_6: 6,
+        matchers_agree_7: 7,
+        matchers_agree_8: 8,
+        matchers_agree_9: 9,
+        matchers_agree_10: 10,
+        matchers_agree_11: 11,
+        matchers_agree_12: 12
+    );
+
+    #[test]
+    fn matchers_agree() {
+        let no_matching: [&[usize]; 5] = [&[1], &[2], &[1], &[0, 2, 4], &[0, 3]];
+        let matching: [&[usize]; 5] = [&[1], &[2], &[1, 4], &[0, 2, 4], &[0, 3]];
+        for matcher in Matching::ALL {
+            assert!(!matcher.has_perfect_matching(&no_matching));
+            assert!(matcher.has_perfect_matching(&matching));
+        }
+    }
+
+    #[test]
+    fn hopcroft_karp_terminates() {
+        // The first version of Hopcroft-Karp looked up the layer of a vertex of side A
+        // by the index of a vertex of side B and looped forever on these.
+        let matching: [&[usize]; 4] = [&[1, 3], &[0, 1], &[0, 2], &[1, 2]];
+        let no_matching: [&[usize]; 4] = [&[1, 2, 3], &[], &[1, 3], &[1]];
+        assert!(maximum_matching_hopcroft_karp(&matching));
+        assert!(!maximum_matching_hopcroft_karp(&no_matching));
+    }
+
+    #[test]
+    fn matchings_and_violators() {
+        let no_matching: [&[usize]; 5] = [&[1], &[2], &[1], &[0, 2, 4], &[0, 3]];
+        let matching: [&[usize]; 5] = [&[1], &[2], &[1, 4], &[0, 2, 4], &[0, 3]];
+        for find in [
+            find_matching_simple,
+            find_matching_hopcroft_karp,
+            find_matching_push_relabel,
+        ] {
+            assert_eq!(find(&no_matching), Err(vec![0, 2]));
+
+            let found = find(&matching).unwrap();
+            for (a, b) in found.iter().enumerate() {
+                assert!(matching[a].contains(b));
+            }
+            let mut partners = found.to_vec();
+            partners.sort();
+            assert_eq!(partners, vec![0, 1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn explained_inferiority() {
+        let a = line([&[0], &[0, 1], &[2]]);
+        let b = line([&[2], &[0, 1], &[0, 1, 2]]);
+        assert_eq!(explain_inferiority(&a, &b), Ok([1, 2, 0]));
+        assert_eq!(explain_inferiority(&b, &a), Err(vec![2]));
+    }
 }
@@ -1,59 +1,505 @@
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
+
 use bitarray::BitArray;
-use maximizer::{active_side, bitarray};
-use std::collections::BTreeSet;
+use itertools::Itertools;
+use maximizer::diagram::Diagram;
+use maximizer::harden::harden_lines_with;
+use maximizer::labels::LabelTable;
+use maximizer::limits::{Limits, Status};
+use maximizer::line_superiority::Matching;
+use maximizer::useless::CacheMode;
+use maximizer::{
+    bitarray, checkpoint, line::Line, new_labels, parse, passive_side_with, resume_active_side,
+    Event, SearchState,
+};
+use std::convert::TryInto;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How the maximal lines of a single constraint are searched.
+struct SearchOptions {
+    /// Labels forbidden in the result.
+    removed: Vec<String>,
+    limits: Limits,
+    /// `None` keeps the default and doesn't report hits.
+    useless_cache: Option<CacheMode>,
+    /// Where and how often the state of the search is saved.
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    resume: Option<PathBuf>,
+    /// How lines are compared, in every mode.
+    matcher: Matching,
+}
+
+impl SearchOptions {
+    /// Panics if a flag was given that only applies to the search for the maximal lines
+    /// of a single file, which `mode` doesn't do.
+    fn reject_in(&self, mode: &str) {
+        let given = [
+            ("--harden", !self.removed.is_empty()),
+            ("--time-limit", self.limits.time.is_some()),
+            ("--done-limit", self.limits.done.is_some()),
+            ("--combination-limit", self.limits.combinations.is_some()),
+            ("--useless-cache", self.useless_cache.is_some()),
+            ("--checkpoint", self.checkpoint.is_some()),
+            ("--resume", self.resume.is_some()),
+        ];
+        if let Some((flag, _)) = given.iter().find(|(_, given)| *given) {
+            panic!("{} can't be combined with {}.", flag, mode);
+        }
+    }
+}
 
 fn main() {
-    let filename = std::env::args()
-        .nth(1)
-        .expect("Please enter an input file as command line argument.");
-    let passive: Vec<Vec<Vec<u8>>> = BufReader::new(File::open(filename).unwrap())
+    let mut args = std::env::args().skip(1);
+    let mut files = vec![];
+    let mut eliminate_round = false;
+    let mut delta = None;
+    let mut diagram = None;
+    let mut options = SearchOptions {
+        removed: vec![],
+        limits: Limits::default(),
+        useless_cache: None,
+        checkpoint: None,
+        checkpoint_interval: Duration::from_secs(600),
+        resume: None,
+        matcher: Matching::default(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--round-eliminate" => eliminate_round = true,
+            "--degree" => {
+                delta = Some(
+                    args.next()
+                        .and_then(|x| x.parse().ok())
+                        .expect("--degree must be followed by a number."),
+                )
+            }
+            "--diagram" => {
+                diagram = Some(
+                    args.next()
+                        .filter(|x| x == "text" || x == "dot")
+                        .expect("--diagram must be followed by text or dot."),
+                )
+            }
+            "--harden" => {
+                options.removed = args
+                    .next()
+                    .and_then(|x| parse::parse_line(&x, None).ok())
+                    .map(|x| x.into_iter().flatten().flatten().collect())
+                    .expect("--harden must be followed by a set of labels.")
+            }
+            "--threads" => rayon::ThreadPoolBuilder::new()
+                .num_threads(
+                    args.next()
+                        .and_then(|x| x.parse().ok())
+                        .expect("--threads must be followed by a number."),
+                )
+                .build_global()
+                .unwrap(),
+            "--time-limit" => {
+                options.limits.time = Some(Duration::from_secs(
+                    args.next()
+                        .and_then(|x| x.parse().ok())
+                        .expect("--time-limit must be followed by a number of seconds."),
+                ))
+            }
+            "--done-limit" => {
+                options.limits.done = Some(
+                    args.next()
+                        .and_then(|x| x.parse().ok())
+                        .expect("--done-limit must be followed by a number."),
+                )
+            }
+            "--combination-limit" => {
+                options.limits.combinations = Some(
+                    args.next()
+                        .and_then(|x| x.parse().ok())
+                        .expect("--combination-limit must be followed by a number."),
+                )
+            }
+            "--matcher" => {
+                options.matcher = match args.next().as_deref() {
+                    Some("simple") => Matching::Simple,
+                    Some("hopcroft-karp") => Matching::HopcroftKarp,
+                    Some("push-relabel") => Matching::PushRelabel,
+                    Some("bitmask") => Matching::Bitmask,
+                    _ => panic!(
+                    "--matcher must be followed by simple, hopcroft-karp, push-relabel or bitmask."
+                ),
+                }
+            }
+            "--useless-cache" => {
+                options.useless_cache = Some(
+                    match args
+                        .next()
+                        .expect("--useless-cache must be followed by off, unbounded or a size.")
+                        .as_str()
+                    {
+                        "off" => CacheMode::Off,
+                        "unbounded" => CacheMode::Unbounded,
+                        x => CacheMode::Bounded(x.parse().expect(
+                            "--useless-cache must be followed by off, unbounded or a size.",
+                        )),
+                    },
+                )
+            }
+            "--checkpoint" => {
+                options.checkpoint = Some(
+                    args.next()
+                        .expect("--checkpoint must be followed by a file name.")
+                        .into(),
+                )
+            }
+            "--checkpoint-interval" => {
+                options.checkpoint_interval = Duration::from_secs(
+                    args.next()
+                        .and_then(|x| x.parse().ok())
+                        .expect("--checkpoint-interval must be followed by a number of seconds."),
+                )
+            }
+            "--resume" => {
+                options.resume = Some(
+                    args.next()
+                        .expect("--resume must be followed by a file name.")
+                        .into(),
+                )
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    if eliminate_round && diagram.is_some() {
+        panic!("--diagram can't be combined with --round-eliminate.");
+    }
+
+    match &files[..] {
+        [active, passive] if eliminate_round => {
+            options.reject_in("--round-eliminate");
+            let active = read_lines(active, delta);
+            let passive = read_lines(passive, delta);
+
+            let alphabet = alphabet_of(active.iter().chain(&passive));
+            let maximal = with_cells!(cells_for(alphabet.len()), |C| {
+                with_degree!(passive[0].len(), |P| {
+                    let (state, _) = search::<C, P>(&passive, &alphabet, &options, false);
+                    state
+                        .into_lines()
+                        .iter()
+                        .map(|line| line.0.iter().map(|set| set.iter().collect()).collect())
+                        .collect()
+                })
+            });
+            eliminate(maximal, &active, &alphabet, options.matcher)
+        }
+        [constraint] if diagram.is_some() => {
+            options.reject_in("--diagram");
+            let constraint = read_lines(constraint, delta);
+
+            let alphabet = alphabet_of(&constraint);
+            with_cells!(cells_for(alphabet.len()), |C| {
+                with_degree!(constraint[0].len(), |D| {
+                    let (state, _) = search::<C, D>(&constraint, &alphabet, &options, false);
+                    let strength = Diagram::new(&state.into_lines(), alphabet.len());
+                    match diagram.as_deref() {
+                        Some("dot") => print!("{}", strength.to_dot(&alphabet)),
+                        _ => print!("{}", strength.to_text(&alphabet)),
+                    }
+                })
+            })
+        }
+        [passive] if !eliminate_round => {
+            let passive = read_lines(passive, delta);
+
+            let alphabet = alphabet_of(&passive);
+            with_cells!(cells_for(alphabet.len()), |C| {
+                with_degree!(passive[0].len(), |D| {
+                    print_active_side::<C, D>(&passive, &alphabet, &options)
+                })
+            })
+        }
+        _ => panic!(
+            "Please enter an input file as command line argument, \
+            or --round-eliminate followed by an active and a passive constraint file. \
+            Use --degree to give the value of Δ in exponents. \
+            Use --diagram text or --diagram dot with a single file to show how strong its labels are. \
+            Use --harden followed by labels to forbid them in the maximal lines of a single file. \
+            Use --checkpoint with a file name to save the search for a single file every \
+            --checkpoint-interval seconds, and --resume with that file and the same input to continue. \
+            Use --time-limit, --done-limit or --combination-limit to stop early \
+            and get the lines found so far, which are also saved to the --checkpoint file. \
+            Use --useless-cache followed by off, unbounded or a size to choose how many \
+            useless lines are remembered and to show how often that helped. \
+            Use --matcher followed by simple, hopcroft-karp, push-relabel or bitmask to choose \
+            how lines are compared. \
+            Use --threads to limit the number of threads, which defaults to the number of cores. \
+            Alphabets, including those created by round elimination, may have up to {} labels, \
+            and configurations may have up to {} labels.",
+            MAX_LABELS, MAX_DEGREE
+        ),
+    }
+}
+
+/// Evaluates `body` with a constant `C` equal to `cells`, which must come from [`cells_for`].
+macro_rules! with_cells {
+    ($cells:expr, |$c:ident| $body:expr) => {
+        with_const!($cells, [1 2 4 8], |$c| $body, unreachable!())
+    };
+}
+
+/// Evaluates `body` with a constant `D` equal to `degree`, which is at most [`MAX_DEGREE`].
+/// Keep in mind that every supported degree gets compiled for every cell count.
+macro_rules! with_degree {
+    ($degree:expr, |$d:ident| $body:expr) => {
+        with_const!(
+            $degree,
+            [1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16],
+            |$d| $body,
+            panic!(
+                "Configurations of more than {} labels are unsupported, but this one has {}.",
+                MAX_DEGREE, $degree
+            )
+        )
+    };
+}
+
+macro_rules! with_const {
+    ($value:expr, [$($n:literal)*], |$name:ident| $body:expr, $otherwise:expr) => {
+        match $value {
+            $($n => {
+                const $name: usize = $n;
+                $body
+            })*
+            _ => $otherwise,
+        }
+    };
+}
+use {with_cells, with_const, with_degree};
+
+/// The most labels that label sets are compiled for, which is 8 cells.
+const MAX_LABELS: usize = 8 * usize::BITS as usize;
+
+/// The largest degree that [`with_degree`] is compiled for.
+const MAX_DEGREE: usize = 16;
+
+/// Returns the number of cells used for label sets over `labels` labels.
+fn cells_for(labels: usize) -> usize {
+    match bitarray::cells_needed(labels) {
+        0..=1 => 1,
+        2 => 2,
+        3..=4 => 4,
+        5..=8 => 8,
+        _ => panic!(
+            "Alphabets of more than {} labels are unsupported, but this one has {} labels.",
+            MAX_LABELS, labels
+        ),
+    }
+}
+
+type Configuration = Vec<Vec<String>>;
+
+fn read_lines(filename: &str, delta: Option<usize>) -> Vec<Configuration> {
+    let lines: Vec<Configuration> = BufReader::new(File::open(filename).unwrap())
         .lines()
-        .map(|line| {
-            line.unwrap()
-                .split_ascii_whitespace()
-                .map(|x| x.bytes().collect())
-                .collect()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            parse::parse_line(&line.unwrap(), delta)
+                .unwrap_or_else(|e| panic!("{}:{}: {}", filename, i + 1, e))
         })
         .collect();
-    let degree = passive[0].len();
 
-    let alphabet: Vec<u8> = passive
+    if lines.is_empty() {
+        panic!("{} contains no configurations.", filename)
+    }
+    if lines.iter().any(|line| line.len() != lines[0].len()) {
+        panic!(
+            "All configurations in {} must have the same degree.",
+            filename
+        )
+    }
+
+    lines
+}
+
+fn alphabet_of<'a>(lines: impl IntoIterator<Item = &'a Configuration>) -> LabelTable {
+    LabelTable::new(
+        lines
+            .into_iter()
+            .flat_map(|line| line.iter().flatten().cloned()),
+    )
+}
+
+fn to_lines<const C: usize, const D: usize>(
+    lines: &[Configuration],
+    alphabet: &LabelTable,
+) -> Vec<Line<C, D>> {
+    lines
         .iter()
-        .flat_map(|line| line.iter().flat_map(|set| set.iter()))
-        .collect::<BTreeSet<_>>()
+        .map(|line| {
+            let sets = line
+                .iter()
+                // SAFETY: always found because `alphabet` has every label in `lines`
+                .map(|s| alphabet.to_set(s).unwrap())
+                .collect::<Vec<_>>();
+            Line(sets.try_into().unwrap())
+        })
+        .collect()
+}
+
+fn show_lines<'a, const C: usize>(
+    lines: impl IntoIterator<Item = &'a [BitArray<C>]>,
+    labels: &LabelTable,
+) -> String {
+    let mut strings = lines
         .into_iter()
-        .cloned()
-        .collect();
+        .map(|line| labels.show_sets(line))
+        .collect::<Vec<_>>();
+    strings.sort();
+    strings.join("\n")
+}
+
+/// Prints the steps and the maximal lines, from which the labels in `removed` are then forbidden.
+fn print_active_side<const C: usize, const D: usize>(
+    passive: &[Configuration],
+    alphabet: &LabelTable,
+    options: &SearchOptions,
+) where
+    [(); D - 1]: Sized,
+{
+    let (state, status) = search::<C, D>(passive, alphabet, options, true);
+    let hits = state.useless.hits;
+
+    let removed = alphabet
+        .to_set(&options.removed)
+        .expect("--harden must only use labels of the constraint.");
+    let done = harden_lines_with(&state.into_lines(), removed, &options.matcher);
+
+    println!("{}", show_lines(done.iter().map(|x| &x.0[..]), alphabet));
+    if options.useless_cache.is_some() {
+        println!("useless cache hits: {}", hits);
+    }
+    let limit = match status {
+        Status::Complete => return,
+        Status::TimeLimit => "time",
+        Status::DoneLimit => "done",
+        Status::CombinationLimit => "combination",
+    };
+    println!("incomplete: stopped by the {} limit", limit);
+}
 
-    if bitarray::cells_needed(alphabet.len()) != 1 {
-        panic!("Alphabets that big are currently unsupported.")
+/// Searches for the maximal lines of `passive` as the `options` say, printing the steps if `log`.
+/// Every mode uses this, so that the search is only compiled once for each cell count and degree.
+fn search<const C: usize, const D: usize>(
+    passive: &[Configuration],
+    alphabet: &LabelTable,
+    options: &SearchOptions,
+    log: bool,
+) -> (SearchState<C, D>, Status)
+where
+    [(); D - 1]: Sized,
+{
+    let input = to_lines(passive, alphabet);
+    let mut state = match &options.resume {
+        Some(path) => checkpoint::load(&input, path).unwrap_or_else(|e| panic!("{}", e)),
+        None => SearchState::new(input.clone()),
+    };
+    state.matcher = options.matcher;
+    if let Some(mode) = options.useless_cache {
+        state.useless.set_mode(mode);
     }
+    let mut last_save = Instant::now();
+    let save = |state: &SearchState<C, D>| {
+        if let Some(path) = &options.checkpoint {
+            if last_save.elapsed() >= options.checkpoint_interval {
+                checkpoint::save(state, &input, path)
+                    .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+                last_save = Instant::now();
+            }
+        }
+    };
 
-    let passive: Vec<Vec<BitArray<1>>> = passive
-        .iter()
-        .map(|line| {
-            line.iter()
-                .map(|s| {
-                    let mut out = bitarray::zero();
-                    for x in s {
-                        // SAFETY: always found because `alphabet` has every symbol in `passive`
-                        out.set(alphabet.binary_search(x).unwrap());
-                    }
-                    out
-                })
-                .collect()
+    let show_line = |line: &Line<C, D>| alphabet.show_line(line);
+    let log = |event: Event<C, D>| match event {
+        _ if !log => {}
+        Event::Found { new, via } => {
+            println!("found: {} via {}", show_line(new), show_line(via))
+        }
+        Event::RemovedFromTodo { old, new } => {
+            println!("removed from todo: {} < {}", show_line(old), show_line(new))
+        }
+        Event::RemovedFromDone { old, new } => {
+            println!("removed from done: {} < {}", show_line(old), show_line(new))
+        }
+    };
+    let status = resume_active_side(&mut state, &options.limits, log, save);
+    if let Some(path) = &options.checkpoint {
+        if !status.is_complete() {
+            checkpoint::save(&state, &input, path)
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        }
+    }
+    (state, status)
+}
+
+/// Prints the result of round elimination given the maximal lines of the passive side,
+/// whose sets are lists of old labels. The rest is only compiled for the active degree
+/// and one size of label sets that fits both alphabets, so that the sizes don't multiply.
+fn eliminate(
+    maximal: Vec<Vec<Vec<usize>>>,
+    active: &[Configuration],
+    alphabet: &LabelTable,
+    matcher: Matching,
+) {
+    let new_labels = maximal.iter().flatten().sorted().dedup().count();
+
+    with_cells!(cells_for(alphabet.len().max(new_labels)), |C| {
+        with_degree!(active[0].len(), |A| {
+            print_next_problem::<C, A>(&maximal, active, alphabet, matcher)
         })
+    })
+}
+
+fn print_next_problem<const C: usize, const A: usize>(
+    maximal: &[Vec<Vec<usize>>],
+    active: &[Configuration],
+    alphabet: &LabelTable,
+    matcher: Matching,
+) {
+    let to_set = |labels: &Vec<usize>| {
+        let mut set: BitArray<C> = bitarray::zero();
+        for l in labels {
+            set.set(*l);
+        }
+        set
+    };
+    let maximal: Vec<Vec<BitArray<C>>> = maximal
+        .iter()
+        .map(|line| line.iter().map(to_set).collect())
+        .collect();
+    let labels: Vec<BitArray<C>> = maximal.iter().flatten().cloned().sorted().dedup().collect();
+    let names = LabelTable::fresh(labels.len());
+
+    let new_active: Vec<Vec<BitArray<C>>> = maximal
+        .iter()
+        .map(|line| new_labels(line, &labels).collect())
         .collect();
+    let new_passive: Vec<Line<C, A>> =
+        passive_side_with(&to_lines::<C, A>(active, alphabet), &labels, &matcher);
 
-    match degree {
-        2 => active_side::<1, 2>(passive, alphabet),
-        3 => active_side::<1, 3>(passive, alphabet),
-        4 => active_side::<1, 4>(passive, alphabet),
-        5 => active_side::<1, 5>(passive, alphabet),
-        9 => active_side::<1, 9>(passive, alphabet),
-        _ => println!("Didn't compile version for degree {}", degree),
+    println!(
+        "active:\n{}\n",
+        show_lines(new_active.iter().map(|x| &x[..]), &names)
+    );
+    println!(
+        "passive:\n{}\n",
+        show_lines(new_passive.iter().map(|x| &x.0[..]), &names)
+    );
+    println!("labels:");
+    for (i, set) in labels.iter().enumerate() {
+        println!("{} = {}", names.name(i), alphabet.show_set(set));
     }
 }
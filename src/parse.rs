@@ -0,0 +1,226 @@
+//! Parsing of constraints written in condensed notation.
+//!
+//! A line is a sequence of label sets separated by whitespace.
+//! A set is either a run of labels like `ABC` or a bracketed run like `[ABC]`.
+//! A label is a single character, which may be followed by digits as in `M0`
+//! or by a parenthesized group as in `X(AB)`. A group on its own like `(AB)`
+//! is a label as well. Inside brackets, labels can be separated by whitespace.
+//! Any set can be followed by an exponent like `A^3`, `[AB]^Δ` or `M^(Δ-1)`
+//! to repeat it. Alternatives separated by `|` stand for separate lines,
+//! so `M^Δ | P O^(Δ-1)` is the same as writing both lines on their own.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Expands one line of condensed notation into the configurations it stands for.
+/// Each configuration is a list of sets of label names.
+/// `degree` is the value of `Δ` in exponents; it is only needed if `Δ` is used.
+pub fn parse_line(line: &str, degree: Option<usize>) -> Result<Vec<Vec<Vec<String>>>, String> {
+    if line.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    line.split('|')
+        .map(|alternative| {
+            let sets = parse_alternative(alternative, degree)?;
+            if sets.is_empty() {
+                Err("Empty alternative".to_owned())
+            } else {
+                Ok(sets)
+            }
+        })
+        .collect()
+}
+
+fn parse_alternative(text: &str, degree: Option<usize>) -> Result<Vec<Vec<String>>, String> {
+    let mut chars = text.chars().peekable();
+    let mut out = vec![];
+
+    loop {
+        skip_whitespace(&mut chars);
+        let mut set = vec![];
+        match chars.peek() {
+            None => break,
+            Some('[') => {
+                chars.next();
+                loop {
+                    skip_whitespace(&mut chars);
+                    match chars.peek() {
+                        Some(']') => {
+                            chars.next();
+                            break;
+                        }
+                        None => return Err("Missing ']'".to_owned()),
+                        Some(_) => set.push(parse_label(&mut chars)?),
+                    }
+                }
+            }
+            Some(_) => {
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '[' | ']' | '^' | '|') {
+                        break;
+                    }
+                    set.push(parse_label(&mut chars)?);
+                }
+            }
+        }
+        if set.is_empty() {
+            return Err("Empty label set".to_owned());
+        }
+
+        let count = if chars.peek() == Some(&'^') {
+            chars.next();
+            parse_exponent(&mut chars, degree)?
+        } else {
+            1
+        };
+        out.extend(std::iter::repeat_n(set, count));
+    }
+
+    Ok(out)
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+/// Parses a label: a character or a parenthesized group, optionally followed by
+/// digits or a parenthesized group. `A`, `M0`, `P12`, `X(AB)` and `(AB)` are labels.
+fn parse_label(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut label = String::new();
+    match chars.next() {
+        Some('(') => parse_group(chars, &mut label)?,
+        Some(c) if is_special(c) => return Err(format!("Unexpected '{}'", c)),
+        Some(c) => {
+            label.push(c);
+            if chars.peek() == Some(&'(') {
+                chars.next();
+                parse_group(chars, &mut label)?;
+                return Ok(label);
+            }
+        }
+        None => return Err("Missing label".to_owned()),
+    }
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        label.push(c);
+        chars.next();
+    }
+    Ok(label)
+}
+
+/// Appends a group whose opening parenthesis has already been consumed.
+fn parse_group(chars: &mut Peekable<Chars>, label: &mut String) -> Result<(), String> {
+    label.push('(');
+    let mut depth = 1;
+    while depth > 0 {
+        let c = chars.next().ok_or("Missing ')'")?;
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if c.is_whitespace() || is_special(c) => {
+                return Err(format!("Unexpected '{}' inside a label", c))
+            }
+            _ => {}
+        }
+        label.push(c);
+    }
+    Ok(())
+}
+
+fn is_special(c: char) -> bool {
+    matches!(c, '[' | ']' | '^' | ')' | '|')
+}
+
+/// Parses `3`, `Δ`, `Δ-1` or any of them in parentheses.
+fn parse_exponent(chars: &mut Peekable<Chars>, degree: Option<usize>) -> Result<usize, String> {
+    let parenthesized = chars.peek() == Some(&'(');
+    if parenthesized {
+        chars.next();
+    }
+
+    let mut text = String::new();
+    while let Some(&c) = chars.peek() {
+        if c == ')' || (!parenthesized && (c.is_whitespace() || is_special(c))) {
+            break;
+        }
+        if !c.is_whitespace() {
+            text.push(c);
+        }
+        chars.next();
+    }
+    if parenthesized && chars.next() != Some(')') {
+        return Err("Missing ')'".to_owned());
+    }
+
+    let number = |s: &str| {
+        s.parse::<usize>()
+            .map_err(|_| format!("Invalid exponent '{}'", text))
+    };
+    if let Some(rest) = text.strip_prefix('Δ') {
+        let degree = degree.ok_or("Δ is used but the degree is not given")?;
+        if rest.is_empty() {
+            Ok(degree)
+        } else if let Some(k) = rest.strip_prefix('-') {
+            degree
+                .checked_sub(number(k)?)
+                .ok_or_else(|| format!("Exponent '{}' is negative", text))
+        } else if let Some(k) = rest.strip_prefix('+') {
+            Ok(degree + number(k)?)
+        } else {
+            Err(format!("Invalid exponent '{}'", text))
+        }
+    } else {
+        number(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sets(line: &str) -> Vec<Vec<String>> {
+        line.split_ascii_whitespace()
+            .map(|x| x.split(',').map(|l| l.to_owned()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn plain_lines_are_unchanged() {
+        assert_eq!(parse_line("A BC", None), Ok(vec![sets("A B,C")]));
+    }
+
+    #[test]
+    fn exponents_and_brackets() {
+        assert_eq!(
+            parse_line("[AB] [BC]^3", None),
+            Ok(vec![sets("A,B B,C B,C B,C")])
+        );
+        assert_eq!(
+            parse_line("M^(Δ-1) P O", Some(4)),
+            Ok(vec![sets("M M M P O")])
+        );
+        assert!(parse_line("M^Δ", None).is_err());
+    }
+
+    #[test]
+    fn long_labels() {
+        assert_eq!(
+            parse_line("M0P1 [X(AB) Δ] (AB)^2", None),
+            Ok(vec![sets("M0,P1 X(AB),Δ (AB) (AB)")])
+        );
+        assert!(parse_line("X(A B)", None).is_err());
+    }
+
+    #[test]
+    fn alternatives() {
+        assert_eq!(
+            parse_line("M^Δ | P O^(Δ-1)", Some(3)),
+            Ok(vec![sets("M M M"), sets("P O O")])
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Relaxations that make a problem easier while keeping its alphabet small.
+//!
+//! Every operation maximizes both constraints again afterwards.
+
+use crate::bitarray::{zero, BitArray};
+use crate::diagram::Diagram;
+use crate::line::Line;
+use crate::{active_side, maximize, Problem};
+
+/// Replaces `from` by `into` in every set of both constraints.
+pub fn merge_labels<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    from: usize,
+    into: usize,
+) -> Problem<C, A, P>
+where
+    [(); A - 1]: Sized,
+    [(); P - 1]: Sized,
+{
+    let replace = |set: BitArray<C>| replaced(set, from, into);
+    maximize(&Problem {
+        active: map_sets(&problem.active, replace),
+        passive: map_sets(&problem.passive, replace),
+    })
+}
+
+/// Allows `label` in every passive set that contains `next_to`,
+/// which makes `label` at least as strong as `next_to`.
+pub fn add_label<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    label: usize,
+    next_to: usize,
+) -> Problem<C, A, P>
+where
+    [(); A - 1]: Sized,
+    [(); P - 1]: Sized,
+{
+    let mut extra = zero();
+    extra.set(label);
+    maximize(&Problem {
+        active: problem.active.clone(),
+        passive: map_sets(&problem.passive, |set| {
+            if set.get(next_to) {
+                set | extra
+            } else {
+                set
+            }
+        }),
+    })
+}
+
+/// Lets nodes output `strong` wherever they could output `weak`, after which `weak` isn't needed.
+/// Returns `None` if `strong` is not at least as strong as `weak` in the passive constraint.
+/// Replacing a label with itself changes nothing.
+pub fn replace_with_stronger<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+    weak: usize,
+    strong: usize,
+) -> Option<Problem<C, A, P>>
+where
+    [(); A - 1]: Sized,
+    [(); P - 1]: Sized,
+{
+    if weak == strong {
+        return Some(maximize(problem));
+    }
+    let passive = active_side(problem.passive.clone());
+    if !Diagram::new(&passive, weak.max(strong) + 1).is_at_least_as_strong(strong, weak) {
+        return None;
+    }
+
+    let mut removed = zero();
+    removed.set(weak);
+    Some(maximize(&Problem {
+        active: map_sets(&problem.active, |set| replaced(set, weak, strong)),
+        passive: map_sets(&passive, |set| set & !removed),
+    }))
+}
+
+fn replaced<const C: usize>(mut set: BitArray<C>, from: usize, into: usize) -> BitArray<C> {
+    if set.get(from) {
+        let mut single = zero();
+        single.set(from);
+        set = set ^ single;
+        set.set(into);
+    }
+    set
+}
+
+/// Applies `f` to every set and drops lines that end up with an empty set or twice.
+pub(crate) fn map_sets<const C: usize, const D: usize>(
+    lines: &[Line<C, D>],
+    f: impl Fn(BitArray<C>) -> BitArray<C>,
+) -> Vec<Line<C, D>> {
+    let mut out: Vec<Line<C, D>> = vec![];
+    for line in lines {
+        let mut sets = line.0;
+        for set in sets.iter_mut() {
+            *set = f(*set);
+        }
+        sets.sort();
+        let line = Line(sets);
+        if line.0.iter().all(|x| *x != zero()) && !out.contains(&line) {
+            out.push(line);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+
+    fn three_coloring() -> Problem<1, 2, 2> {
+        Problem {
+            active: vec![line([&[0], &[0]]), line([&[1], &[1]]), line([&[2], &[2]])],
+            passive: vec![line([&[0], &[1, 2]]), line([&[1], &[2]])],
+        }
+    }
+
+    #[test]
+    fn merging_colors() {
+        let problem = merge_labels(&three_coloring(), 2, 1);
+        assert_eq!(problem.active.len(), 2);
+        assert!(problem.active.contains(&line([&[1], &[1]])));
+        // The merged color may now be next to itself.
+        assert_eq!(problem.passive, vec![line([&[0, 1], &[1]])]);
+    }
+
+    #[test]
+    fn adding_a_label() {
+        let problem = add_label(&three_coloring(), 0, 1);
+        assert!(problem.passive.contains(&line([&[0, 1], &[0, 2]])));
+    }
+
+    #[test]
+    fn replacing_with_a_stronger_label() {
+        assert!(replace_with_stronger(&three_coloring(), 0, 1).is_none());
+
+        let problem = Problem {
+            active: vec![line([&[0], &[1]])],
+            passive: vec![line([&[0, 1], &[1]])],
+        };
+        let problem = replace_with_stronger(&problem, 0, 1).unwrap();
+        assert_eq!(problem.active, vec![line([&[1], &[1]])]);
+        assert_eq!(problem.passive, vec![line([&[1], &[1]])]);
+    }
+
+    #[test]
+    fn replacing_a_label_with_itself() {
+        let problem = three_coloring();
+        let replaced = replace_with_stronger(&problem, 1, 1).unwrap();
+        assert_eq!(replaced.active, problem.active);
+        assert_eq!(replaced.passive, maximize(&problem).passive);
+    }
+}
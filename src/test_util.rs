@@ -0,0 +1,20 @@
+//! Helpers shared by the tests of several modules.
+
+use crate::bitarray::zero;
+use crate::line::Line;
+
+/// Builds a line whose sets contain the given labels.
+pub fn line<const D: usize>(sets: [&[usize]; D]) -> Line<1, D> {
+    wide_line(sets)
+}
+
+/// Like [`line`] with `C` cells per set.
+pub fn wide_line<const C: usize, const D: usize>(sets: [&[usize]; D]) -> Line<C, D> {
+    let mut out = [zero(); D];
+    for (o, set) in out.iter_mut().zip(&sets) {
+        for x in set.iter() {
+            o.set(*x);
+        }
+    }
+    Line(out)
+}
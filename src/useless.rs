@@ -0,0 +1,213 @@
+use crate::bitarray::BitArray;
+use itertools::Either;
+use std::collections::{HashMap, HashSet};
+
+/// How many of the lines known to be useless are remembered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CacheMode {
+    Off,
+    Unbounded,
+    /// At most this many lines. When full, lines that weren't hit recently are forgotten first.
+    Bounded(usize),
+}
+
+/// Sorted lines that are known to be inferior to some line, so that they don't have
+/// to be compared against `todo` and `done` again.
+///
+/// A bounded cache evicts with the clock algorithm: every hit marks a line,
+/// and the hand moving over the slots clears marks until it finds an unmarked line to replace.
+#[derive(Clone, Debug)]
+pub struct UselessCache<const C: usize, const D: usize> {
+    mode: CacheMode,
+    storage: Storage<[BitArray<C>; D]>,
+    /// How often a lookup found a line, which saved comparing it.
+    pub hits: usize,
+}
+
+#[derive(Clone, Debug)]
+enum Storage<K> {
+    /// Without a bound there is nothing to evict, so every line is stored once.
+    Set(HashSet<K>),
+    /// Every line is stored in a slot with its mark and as a key of `positions`,
+    /// which is fine because there are only so many of them.
+    Clock {
+        slots: Vec<(K, bool)>,
+        positions: HashMap<K, usize>,
+        hand: usize,
+    },
+}
+
+impl<const C: usize, const D: usize> Default for UselessCache<C, D> {
+    fn default() -> Self {
+        Self::new(CacheMode::Unbounded)
+    }
+}
+
+impl<const C: usize, const D: usize> PartialEq for UselessCache<C, D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|x| other.contains(x))
+    }
+}
+
+impl<const C: usize, const D: usize> UselessCache<C, D> {
+    pub fn new(mode: CacheMode) -> Self {
+        let storage = match mode {
+            CacheMode::Off | CacheMode::Unbounded => Storage::Set(HashSet::new()),
+            CacheMode::Bounded(_) => Storage::Clock {
+                slots: vec![],
+                positions: HashMap::new(),
+                hand: 0,
+            },
+        };
+        Self {
+            mode,
+            storage,
+            hits: 0,
+        }
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        self.mode
+    }
+
+    /// Changes the mode, forgetting lines if there are too many for it.
+    pub fn set_mode(&mut self, mode: CacheMode) {
+        let hits = self.hits;
+        let old = std::mem::replace(self, Self::new(mode));
+        self.hits = hits;
+        match old.storage {
+            Storage::Set(lines) => self.extend(lines),
+            Storage::Clock { slots, .. } => self.extend(slots.into_iter().map(|(x, _)| x)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Set(lines) => lines.len(),
+            Storage::Clock { slots, .. } => slots.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[BitArray<C>; D]> {
+        match &self.storage {
+            Storage::Set(lines) => Either::Left(lines.iter()),
+            Storage::Clock { slots, .. } => Either::Right(slots.iter().map(|(x, _)| x)),
+        }
+    }
+
+    /// Looks up a line without counting it as a hit, so it can be used from several threads.
+    /// Hits have to be reported with [`UselessCache::record_hits`].
+    pub fn contains(&self, line: &[BitArray<C>; D]) -> bool {
+        match &self.storage {
+            Storage::Set(lines) => lines.contains(line),
+            Storage::Clock { positions, .. } => positions.contains_key(line),
+        }
+    }
+
+    pub fn record_hits<'a>(&mut self, lines: impl IntoIterator<Item = &'a [BitArray<C>; D]>) {
+        for line in lines {
+            self.hits += 1;
+            if let Storage::Clock {
+                slots, positions, ..
+            } = &mut self.storage
+            {
+                if let Some(&i) = positions.get(line) {
+                    slots[i].1 = true;
+                }
+            }
+        }
+    }
+
+    pub fn insert(&mut self, line: [BitArray<C>; D]) {
+        let capacity = match self.mode {
+            CacheMode::Off => return,
+            CacheMode::Unbounded => usize::MAX,
+            CacheMode::Bounded(0) => return,
+            CacheMode::Bounded(x) => x,
+        };
+        let (slots, positions, hand) = match &mut self.storage {
+            Storage::Set(lines) => {
+                lines.insert(line);
+                return;
+            }
+            Storage::Clock {
+                slots,
+                positions,
+                hand,
+            } => (slots, positions, hand),
+        };
+        if positions.contains_key(&line) {
+            return;
+        }
+
+        if slots.len() < capacity {
+            positions.insert(line, slots.len());
+            slots.push((line, false));
+            return;
+        }
+        loop {
+            let (old, marked) = &mut slots[*hand];
+            if *marked {
+                *marked = false;
+                *hand = (*hand + 1) % capacity;
+            } else {
+                positions.remove(old);
+                positions.insert(line, *hand);
+                *old = line;
+                *hand = (*hand + 1) % capacity;
+                return;
+            }
+        }
+    }
+}
+
+impl<const C: usize, const D: usize> Extend<[BitArray<C>; D]> for UselessCache<C, D> {
+    fn extend<T: IntoIterator<Item = [BitArray<C>; D]>>(&mut self, lines: T) {
+        for line in lines {
+            self.insert(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitarray::zero;
+
+    fn key(label: usize) -> [BitArray<1>; 2] {
+        let mut set = zero();
+        set.set(label);
+        [set, set]
+    }
+
+    #[test]
+    fn bounded_cache_keeps_hit_lines() {
+        let mut cache = UselessCache::new(CacheMode::Bounded(2));
+        cache.insert(key(0));
+        cache.insert(key(1));
+        cache.record_hits(&[key(0)]);
+        cache.insert(key(2));
+
+        assert!(cache.contains(&key(0)));
+        assert!(!cache.contains(&key(1)));
+        assert!(cache.contains(&key(2)));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.hits, 1);
+    }
+
+    #[test]
+    fn disabled_cache_stays_empty() {
+        let mut cache = UselessCache::new(CacheMode::Off);
+        cache.insert(key(0));
+        assert!(!cache.contains(&key(0)));
+
+        let mut cache = UselessCache::new(CacheMode::Unbounded);
+        cache.extend((0..10).map(key));
+        cache.set_mode(CacheMode::Bounded(3));
+        assert_eq!(cache.len(), 3);
+    }
+}
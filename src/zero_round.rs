@@ -0,0 +1,148 @@
+use crate::bitarray::{zero, BitArray};
+use crate::line::Line;
+use crate::{active_side, Problem};
+
+/// Shows that no node can pick labels for its ports without knowing anything about its neighbors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortNumberingProof<const C: usize> {
+    /// Every set of labels that can be combined arbitrarily on the passive side
+    /// is a subset of one of these.
+    pub label_sets: Vec<BitArray<C>>,
+    /// `blocked[i][j]` is a position of the `j`th active line that contains
+    /// no label from `label_sets[i]`.
+    pub blocked: Vec<Vec<usize>>,
+}
+
+/// Shows that there is no label that a node could put on all of its ports.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoPortNumberingProof<const C: usize> {
+    /// The labels that some active line allows on every port.
+    pub active_labels: BitArray<C>,
+    /// The labels that some passive line allows on every port. Disjoint from `active_labels`.
+    pub passive_labels: BitArray<C>,
+}
+
+/// Decides whether the problem can be solved in zero rounds when nodes know their port numbering.
+///
+/// Every node has to output the same active configuration, and as neighbors can be connected
+/// via any ports, every passive configuration made of its labels has to be allowed.
+/// Returns that configuration as label indices or a proof that there is none.
+pub fn zero_round_solvable<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+) -> Result<[usize; A], PortNumberingProof<C>>
+where
+    [(); P - 1]: Sized,
+{
+    // A set of labels can be combined arbitrarily if and only if
+    // it is contained in every set of some maximal passive line.
+    let mut label_sets: Vec<BitArray<C>> = vec![];
+    for line in active_side(problem.passive.clone()) {
+        let set = intersection(&line);
+        if set != zero() && !label_sets.iter().any(|x| *x & set == set) {
+            label_sets.retain(|x| *x & set != *x);
+            label_sets.push(set);
+        }
+    }
+
+    let mut blocked = vec![];
+    for set in &label_sets {
+        let mut blocked_lines = vec![];
+        for line in &problem.active {
+            match line.0.iter().position(|x| *x & *set == zero()) {
+                Some(i) => blocked_lines.push(i),
+                None => {
+                    let mut out = [0; A];
+                    for (o, x) in out.iter_mut().zip(&line.0) {
+                        // SAFETY: nonempty as the position wasn't blocked
+                        *o = (*x & *set).iter().next().unwrap();
+                    }
+                    return Ok(out);
+                }
+            }
+        }
+        blocked.push(blocked_lines);
+    }
+
+    Err(PortNumberingProof {
+        label_sets,
+        blocked,
+    })
+}
+
+/// Decides whether the problem can be solved in zero rounds without port numbering,
+/// which means that every node has to put the same label on all of its ports.
+/// Returns that label or a proof that there is none.
+pub fn zero_round_solvable_without_ports<const C: usize, const A: usize, const P: usize>(
+    problem: &Problem<C, A, P>,
+) -> Result<usize, NoPortNumberingProof<C>> {
+    let active_labels = on_all_ports(&problem.active);
+    let passive_labels = on_all_ports(&problem.passive);
+
+    (active_labels & passive_labels)
+        .iter()
+        .next()
+        .ok_or(NoPortNumberingProof {
+            active_labels,
+            passive_labels,
+        })
+}
+
+/// The labels that some line allows at every position.
+fn on_all_ports<const C: usize, const D: usize>(lines: &[Line<C, D>]) -> BitArray<C> {
+    lines.iter().map(intersection).fold(zero(), |a, b| a | b)
+}
+
+/// The labels allowed at every position of the line.
+fn intersection<const C: usize, const D: usize>(line: &Line<C, D>) -> BitArray<C> {
+    line.0.iter().fold(!zero(), |a, b| a & *b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::line;
+
+    #[test]
+    fn needs_port_numbering() {
+        let problem = Problem {
+            active: vec![line([&[0], &[1]])],
+            passive: vec![line([&[0, 1], &[0, 1]])],
+        };
+        assert_eq!(zero_round_solvable(&problem), Ok([0, 1]));
+        assert_eq!(
+            zero_round_solvable_without_ports(&problem),
+            Err(NoPortNumberingProof {
+                active_labels: zero(),
+                passive_labels: line([&[0, 1]]).0[0],
+            })
+        );
+    }
+
+    #[test]
+    fn sinkless_orientation() {
+        // 0 is outgoing and 1 incoming
+        let problem = Problem {
+            active: vec![line([&[0], &[0, 1], &[0, 1]])],
+            passive: vec![line([&[0], &[1]])],
+        };
+        assert_eq!(
+            zero_round_solvable(&problem),
+            Err(PortNumberingProof {
+                label_sets: vec![],
+                blocked: vec![]
+            })
+        );
+        assert!(zero_round_solvable_without_ports(&problem).is_err());
+    }
+
+    #[test]
+    fn blocked_positions() {
+        let problem = Problem {
+            active: vec![line([&[0], &[2]]), line([&[2], &[2]])],
+            passive: vec![line([&[0, 1], &[0, 1]]), line([&[1], &[1]])],
+        };
+        let proof = zero_round_solvable(&problem).unwrap_err();
+        assert_eq!(proof.label_sets, vec![line([&[0, 1]]).0[0]]);
+        assert_eq!(proof.blocked, vec![vec![1, 0]]);
+    }
+}